@@ -0,0 +1,81 @@
+// Times the solver's scoring against the HashSet-based Secret::compare it used to be built on. Run with `cargo bench`.
+use itertools::Itertools;
+use pbfweb::pbf::candidates::{compare_small_symbols, compare_symbols, Symbols, MAX_LENGTH};
+use pbfweb::pbf::solver::GuessState;
+use pbfweb::pbf::{PbfStats, Scoring};
+use pbfweb::secret::Secret;
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::Instant;
+
+const LENGTH: usize = 5;
+const ITERATIONS: u32 = 200;
+
+fn bench<R>(name: &str, mut f: impl FnMut() -> R) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    println!("{:<40} {:>10.1?}", name, start.elapsed() / ITERATIONS);
+}
+
+// The same number of evenly spread candidates the solver samples, as Secrets the way the solver used to store every one of them.
+fn secrets() -> Vec<Secret<u8>> {
+    std::iter::repeat_n(0..10u8, LENGTH)
+        .multi_cartesian_product()
+        .step_by(100)
+        .map(Secret::new)
+        .collect()
+}
+
+fn symbols(guess: &[u8]) -> Symbols {
+    let mut symbols = [0; MAX_LENGTH];
+    symbols[..guess.len()].copy_from_slice(guess);
+    symbols
+}
+
+// What score_guess did before candidates were packed.
+fn score_with_secrets(secrets: &[Secret<u8>], guess: &[u8]) -> usize {
+    let mut partitions: HashMap<PbfStats, usize> = HashMap::new();
+    for secret in secrets {
+        *partitions.entry(secret.compare(guess)).or_insert(0) += 1;
+    }
+    secrets.len() - partitions.values().max().cloned().unwrap_or(0)
+}
+
+fn main() {
+    let guess: Vec<u8> = vec![0, 1, 2, 3, 4];
+    let state = GuessState::new((0..10).collect(), LENGTH);
+    let secrets = secrets();
+    let secret_symbols: Vec<Symbols> = secrets
+        .iter()
+        .map(|secret| symbols(secret.as_guess()))
+        .collect();
+    let guess_symbols = symbols(&guess);
+
+    println!("Comparing one guess with {} candidates:", secrets.len());
+    bench("Secret::compare", || {
+        secrets
+            .iter()
+            .map(|secret| secret.compare(&guess).f)
+            .sum::<i32>()
+    });
+    bench("compare_symbols", || {
+        secret_symbols
+            .iter()
+            .map(|secret| compare_symbols(secret, &guess_symbols, LENGTH, Scoring::Set).f)
+            .sum::<i32>()
+    });
+    bench("compare_small_symbols", || {
+        secret_symbols
+            .iter()
+            .map(|secret| compare_small_symbols(secret, &guess_symbols, LENGTH, Scoring::Set).f)
+            .sum::<i32>()
+    });
+
+    println!("Scoring one guess:");
+    bench("score_guess with Secrets", || {
+        score_with_secrets(&secrets, &guess)
+    });
+    bench("GuessState::score_guess", || state.score_guess(&guess));
+}
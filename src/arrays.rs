@@ -2,8 +2,8 @@ use js_sys::{ArrayBuffer, Uint8Array};
 
 pub fn make_typed_array(arr: &[u8]) -> Uint8Array {
     let out_arr = Uint8Array::new_with_length(arr.len() as u32);
-    for i in 0..arr.len() {
-        out_arr.set_index(i as u32, arr[i])
+    for (i, byte) in arr.iter().enumerate() {
+        out_arr.set_index(i as u32, *byte)
     }
     out_arr
 }
@@ -0,0 +1,10 @@
+// Server that keeps secrets for the web app, so the guesser's browser only ever sees a game id and the results of their guesses.
+// Run with `cargo run --bin custody [address]` and build the web app with PBF_CUSTODY_URL set to where it listens, e.g. http://localhost:9002.
+const DEFAULT_ADDRESS: &str = "127.0.0.1:9002";
+
+fn main() {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    pbfweb::server::custody::serve(&address);
+}
@@ -0,0 +1,375 @@
+// Server for the group mode: a host opens a lobby with a secret, and any number of guessers race to crack it.
+// Run with `cargo run --bin lobby [address]`. It listens on 127.0.0.1:9003 unless given another address, which is where the web app looks for it unless it was built with PBF_LOBBY_URL set.
+use pbfweb::lobby::{is_better, sort_leaderboard, LeaderboardEntry, LobbyMessage, PlayerGuesses};
+use pbfweb::pbf::PbfStats;
+use pbfweb::secret::Secret;
+use pbfweb::server::connection::{Connection, Outbox, Vacancy};
+use pbfweb::server::websocket::WebSocket;
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:9003";
+
+// Longer secrets wouldn't be much of a race.
+const MAX_SECRET_LENGTH: usize = 16;
+const MAX_NICKNAME_LENGTH: usize = 32;
+
+struct Player {
+    id: String,
+    nickname: String,
+    guesses: Vec<(String, PbfStats)>,
+    connection: Connection,
+}
+
+struct Lobby {
+    host: String,
+    host_connection: Connection,
+    secret: Secret<char>,
+    secret_string: String,
+    players: Vec<Player>,
+    // The winner's nickname, once someone has cracked the secret.
+    winner: Option<String>,
+    vacancy: Vacancy,
+}
+
+#[derive(Clone, Copy)]
+enum Role {
+    Host,
+    // Index into the lobby's players.
+    Guesser(usize),
+}
+
+type Lobbies = Arc<Mutex<HashMap<String, Lobby>>>;
+
+fn validate_name(name: &str, what: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err(format!("Enter a {}", what));
+    }
+    if name.chars().count() > MAX_NICKNAME_LENGTH {
+        return Err(format!(
+            "The {} can't be longer than {} characters",
+            what, MAX_NICKNAME_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+impl Lobby {
+    fn length(&self) -> usize {
+        self.secret_string.chars().count()
+    }
+
+    // None if the role belongs to a lobby that has since been replaced by another of the same name.
+    fn connection(&mut self, role: Role) -> Option<&mut Connection> {
+        match role {
+            Role::Host => Some(&mut self.host_connection),
+            Role::Guesser(index) => self
+                .players
+                .get_mut(index)
+                .map(|player| &mut player.connection),
+        }
+    }
+
+    fn occupied(&self) -> bool {
+        self.host_connection.is_connected()
+            || self
+                .players
+                .iter()
+                .any(|player| player.connection.is_connected())
+    }
+
+    fn broadcast(&self, outbox: &mut Outbox, message: &LobbyMessage) {
+        outbox.push(&self.host_connection, message.encode());
+        for player in &self.players {
+            outbox.push(&player.connection, message.encode());
+        }
+    }
+
+    // How many guesses everyone has made and the best result each has had, but never the guesses themselves.
+    fn leaderboard(&self) -> LobbyMessage {
+        let mut entries: Vec<LeaderboardEntry> = self
+            .players
+            .iter()
+            .map(|player| LeaderboardEntry {
+                nickname: player.nickname.clone(),
+                guesses: player.guesses.len(),
+                best: player
+                    .guesses
+                    .iter()
+                    .fold(None, |best, (_, stats)| match best {
+                        Some(best) if !is_better(*stats, best) => Some(best),
+                        _ => Some(*stats),
+                    }),
+            })
+            .collect();
+        sort_leaderboard(&mut entries);
+        LobbyMessage::Leaderboard { entries }
+    }
+
+    fn finished(&self) -> Option<LobbyMessage> {
+        self.winner.as_ref().map(|winner| LobbyMessage::Finished {
+            winner: winner.clone(),
+            secret: self.secret_string.clone(),
+            guesses: self
+                .players
+                .iter()
+                .map(|player| PlayerGuesses {
+                    nickname: player.nickname.clone(),
+                    guesses: player.guesses.clone(),
+                })
+                .collect(),
+        })
+    }
+
+    // Brings a host or guesser who just (re)connected up to date, apart from the leaderboard.
+    fn welcome(&mut self, outbox: &mut Outbox, role: Role) {
+        let joined = LobbyMessage::Joined {
+            length: self.length(),
+        };
+        let scored: Vec<LobbyMessage> = match role {
+            Role::Host => Vec::new(),
+            Role::Guesser(index) => self.players[index]
+                .guesses
+                .iter()
+                .map(|(guess, stats)| LobbyMessage::Scored {
+                    guess: guess.clone(),
+                    stats: *stats,
+                })
+                .collect(),
+        };
+        let finished = self.finished();
+        if let Some(connection) = self.connection(role) {
+            outbox.push(connection, joined.encode());
+            for message in &scored {
+                outbox.push(connection, message.encode());
+            }
+            if let Some(finished) = &finished {
+                outbox.push(connection, finished.encode());
+            }
+        }
+    }
+}
+
+fn open(
+    lobbies: &Lobbies,
+    outbox: &mut Outbox,
+    (connection_id, socket): (usize, WebSocket),
+    lobby_name: String,
+    player: String,
+    secret: String,
+) -> Result<(String, Role), String> {
+    validate_name(&lobby_name, "lobby name")?;
+    let length = secret.chars().count();
+    if length == 0 || length > MAX_SECRET_LENGTH {
+        return Err(format!(
+            "The secret must be between 1 and {} characters long",
+            MAX_SECRET_LENGTH
+        ));
+    }
+    let mut lobbies = lobbies.lock().unwrap();
+    // Opening is the only time lobbies are added, so it's a good time to get rid of old ones.
+    let now = Instant::now();
+    lobbies.retain(|_, lobby| !lobby.vacancy.expired(now));
+    let lobby = lobbies.entry(lobby_name.clone()).or_insert_with(|| Lobby {
+        host: player.clone(),
+        host_connection: Connection::default(),
+        secret: Secret::new(secret.chars().collect()),
+        secret_string: secret,
+        players: Vec::new(),
+        winner: None,
+        vacancy: Vacancy::default(),
+    });
+    if lobby.host != player {
+        return Err("That lobby is already open. Pick another name".to_string());
+    }
+    lobby.host_connection.connect(connection_id, socket);
+    lobby.vacancy.update(true);
+    lobby.welcome(outbox, Role::Host);
+    outbox.push(&lobby.host_connection, lobby.leaderboard().encode());
+    Ok((lobby_name, Role::Host))
+}
+
+fn join(
+    lobbies: &Lobbies,
+    outbox: &mut Outbox,
+    (connection_id, socket): (usize, WebSocket),
+    lobby_name: String,
+    player: String,
+    nickname: String,
+) -> Result<(String, Role), String> {
+    validate_name(&nickname, "nickname")?;
+    let mut lobbies = lobbies.lock().unwrap();
+    let lobby = lobbies
+        .get_mut(&lobby_name)
+        .ok_or("There's no lobby with that name")?;
+    let index = match lobby.players.iter().position(|p| p.id == player) {
+        Some(index) => index,
+        None if lobby.players.iter().any(|p| p.nickname == nickname) => {
+            return Err("Someone in that lobby already has that nickname".to_string())
+        }
+        None => {
+            lobby.players.push(Player {
+                id: player,
+                nickname,
+                guesses: Vec::new(),
+                connection: Connection::default(),
+            });
+            lobby.players.len() - 1
+        }
+    };
+    lobby.players[index]
+        .connection
+        .connect(connection_id, socket);
+    lobby.vacancy.update(true);
+    lobby.welcome(outbox, Role::Guesser(index));
+    // Everyone else sees the new player on the leaderboard.
+    lobby.broadcast(outbox, &lobby.leaderboard());
+    Ok((lobby_name, Role::Guesser(index)))
+}
+
+fn guess(
+    lobbies: &Lobbies,
+    outbox: &mut Outbox,
+    lobby_name: &str,
+    role: Role,
+    connection_id: usize,
+    guess: String,
+) -> Result<(), String> {
+    let mut lobbies = lobbies.lock().unwrap();
+    let lobby = lobbies.get_mut(lobby_name).ok_or("The lobby is gone")?;
+    let index = match role {
+        Role::Guesser(index) => index,
+        Role::Host => return Err("The host can't guess their own secret".to_string()),
+    };
+    // A connection the player has since replaced no longer speaks for them.
+    if !matches!(lobby.connection(role), Some(connection) if connection.is(connection_id)) {
+        return Err("You have joined this lobby again somewhere else".to_string());
+    }
+    if lobby.winner.is_some() {
+        return Err("The game is over".to_string());
+    }
+    if guess.chars().count() != lobby.length() {
+        return Err(format!(
+            "Guesses must be {} characters long",
+            lobby.length()
+        ));
+    }
+    let stats = lobby.secret.compare(guess.chars());
+    let player = &mut lobby.players[index];
+    player.guesses.push((guess.clone(), stats));
+    outbox.push(
+        &player.connection,
+        LobbyMessage::Scored { guess, stats }.encode(),
+    );
+    if stats.f as usize == lobby.length() {
+        lobby.winner = Some(lobby.players[index].nickname.clone());
+    }
+    lobby.broadcast(outbox, &lobby.leaderboard());
+    if let Some(finished) = lobby.finished() {
+        lobby.broadcast(outbox, &finished);
+    }
+    Ok(())
+}
+
+fn leave(lobbies: &Lobbies, lobby_name: &str, role: Role, connection_id: usize) {
+    let mut lobbies = lobbies.lock().unwrap();
+    if let Some(lobby) = lobbies.get_mut(lobby_name) {
+        let left = lobby
+            .connection(role)
+            .map(|connection| connection.disconnect(connection_id))
+            .unwrap_or(false);
+        if left {
+            let occupied = lobby.occupied();
+            lobby.vacancy.update(occupied);
+        }
+    }
+}
+
+fn handle(lobbies: Lobbies, connection_id: usize, mut socket: WebSocket) {
+    // The lobby and who this connection is in it, once they've joined.
+    let mut joined: Option<(String, Role)> = None;
+    while let Ok(Some(text)) = socket.read_message() {
+        // Each step below locks the lobbies and leaves what it has to say here, so it's sent after the lock is released.
+        let mut outbox = Outbox::default();
+        let result = match (LobbyMessage::decode(&text), &joined) {
+            (
+                Ok(LobbyMessage::Open {
+                    lobby,
+                    player,
+                    secret,
+                }),
+                None,
+            ) => socket
+                .try_clone()
+                .map_err(|e| e.to_string())
+                .and_then(|clone| {
+                    open(
+                        &lobbies,
+                        &mut outbox,
+                        (connection_id, clone),
+                        lobby,
+                        player,
+                        secret,
+                    )
+                })
+                .map(|lobby| joined = Some(lobby)),
+            (
+                Ok(LobbyMessage::Join {
+                    lobby,
+                    player,
+                    nickname,
+                }),
+                None,
+            ) => socket
+                .try_clone()
+                .map_err(|e| e.to_string())
+                .and_then(|clone| {
+                    join(
+                        &lobbies,
+                        &mut outbox,
+                        (connection_id, clone),
+                        lobby,
+                        player,
+                        nickname,
+                    )
+                })
+                .map(|lobby| joined = Some(lobby)),
+            (Ok(LobbyMessage::Guess { guess: g }), Some((lobby, role))) => {
+                guess(&lobbies, &mut outbox, lobby, *role, connection_id, g)
+            }
+            (Ok(_), _) => Err("Unexpected message".to_string()),
+            (Err(message), _) => Err(message),
+        };
+        outbox.send();
+        if let Err(message) = result {
+            let _ignored = socket.send(&LobbyMessage::Error { message }.encode());
+        }
+    }
+    if let Some((lobby, role)) = joined {
+        leave(&lobbies, &lobby, role, connection_id);
+    }
+}
+
+fn main() {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    let listener = TcpListener::bind(&address).expect("Could not listen on the address");
+    println!("Lobby server listening on ws://{}", address);
+    let lobbies: Lobbies = Arc::new(Mutex::new(HashMap::new()));
+    for (connection_id, stream) in listener.incoming().enumerate() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        let lobbies = lobbies.clone();
+        thread::spawn(move || {
+            if let Ok(socket) = WebSocket::accept(stream) {
+                handle(lobbies, connection_id, socket);
+            }
+        });
+    }
+}
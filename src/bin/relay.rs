@@ -0,0 +1,204 @@
+// Relay for the head-to-head mode: pairs up two players in a room and passes their messages between them.
+// Run with `cargo run --bin relay [address]`. It listens on 127.0.0.1:9001 unless given another address, which is where the web app looks for it unless it was built with PBF_RELAY_URL set.
+use pbfweb::relay::RelayMessage;
+use pbfweb::server::connection::{Connection, Outbox, Vacancy};
+use pbfweb::server::websocket::WebSocket;
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:9001";
+
+struct Seat {
+    player: String,
+
+    // Every message this player sent, so the other player can catch up after reconnecting.
+    sent: Vec<String>,
+
+    connection: Connection,
+}
+
+#[derive(Default)]
+struct Room {
+    seats: Vec<Seat>,
+    vacancy: Vacancy,
+}
+
+type Rooms = Arc<Mutex<HashMap<String, Room>>>;
+
+fn join(
+    rooms: &Rooms,
+    outbox: &mut Outbox,
+    connection_id: usize,
+    socket: &WebSocket,
+    room_name: String,
+    player: String,
+    seen: usize,
+) -> Result<(String, usize), String> {
+    let mut rooms = rooms.lock().unwrap();
+    // Joining is the only time rooms are added, so it's a good time to get rid of old ones.
+    let now = Instant::now();
+    rooms.retain(|_, room| !room.vacancy.expired(now));
+    let room = rooms.entry(room_name.clone()).or_default();
+    let seat = match room.seats.iter().position(|seat| seat.player == player) {
+        Some(seat) => seat,
+        None if room.seats.len() < 2 => {
+            room.seats.push(Seat {
+                player,
+                sent: Vec::new(),
+                connection: Connection::default(),
+            });
+            room.seats.len() - 1
+        }
+        None => return Err("That room already has two players".to_string()),
+    };
+    let socket = socket.try_clone().map_err(|e| e.to_string())?;
+    room.seats[seat].connection.connect(connection_id, socket);
+    room.vacancy.update(true);
+
+    let received = room.seats[seat].sent.len();
+    let peer = 1 - seat;
+    let peer_messages: Vec<String> = room
+        .seats
+        .get(peer)
+        .map(|peer| peer.sent.iter().skip(seen).cloned().collect())
+        .unwrap_or_default();
+    let peer_connected = room
+        .seats
+        .get(peer)
+        .map(|peer| peer.connection.is_connected())
+        .unwrap_or(false);
+
+    let connection = &room.seats[seat].connection;
+    outbox.push(connection, RelayMessage::Joined { seat, received }.encode());
+    for message in peer_messages {
+        outbox.push(connection, message);
+    }
+    outbox.push(
+        connection,
+        RelayMessage::Peer {
+            connected: peer_connected,
+        }
+        .encode(),
+    );
+    if let Some(peer) = room.seats.get(peer) {
+        outbox.push(
+            &peer.connection,
+            RelayMessage::Peer { connected: true }.encode(),
+        );
+    }
+    Ok((room_name, seat))
+}
+
+fn forward(
+    rooms: &Rooms,
+    outbox: &mut Outbox,
+    room_name: &str,
+    seat: usize,
+    connection_id: usize,
+    message: String,
+) {
+    let mut rooms = rooms.lock().unwrap();
+    if let Some(room) = rooms.get_mut(room_name) {
+        // A connection the player has since replaced no longer speaks for them.
+        match room.seats.get_mut(seat) {
+            Some(sender) if sender.connection.is(connection_id) => {
+                sender.sent.push(message.clone())
+            }
+            _ => return,
+        }
+        if let Some(peer) = room.seats.get(1 - seat) {
+            outbox.push(&peer.connection, message);
+        }
+    }
+}
+
+fn leave(rooms: &Rooms, outbox: &mut Outbox, room_name: &str, seat: usize, connection_id: usize) {
+    let mut rooms = rooms.lock().unwrap();
+    if let Some(room) = rooms.get_mut(room_name) {
+        let left = room
+            .seats
+            .get_mut(seat)
+            .map(|leaving| leaving.connection.disconnect(connection_id))
+            .unwrap_or(false);
+        if !left {
+            return;
+        }
+        if let Some(peer) = room.seats.get(1 - seat) {
+            outbox.push(
+                &peer.connection,
+                RelayMessage::Peer { connected: false }.encode(),
+            );
+        }
+        let occupied = room.seats.iter().any(|seat| seat.connection.is_connected());
+        room.vacancy.update(occupied);
+    }
+}
+
+fn handle(rooms: Rooms, connection_id: usize, mut socket: WebSocket) {
+    // The room and seat, once the player has joined.
+    let mut joined: Option<(String, usize)> = None;
+    while let Ok(Some(text)) = socket.read_message() {
+        // Each step below locks the rooms and leaves what it has to say here, so it's sent after the lock is released.
+        let mut outbox = Outbox::default();
+        match (RelayMessage::decode(&text), &joined) {
+            (Ok(RelayMessage::Join { room, player, seen }), None) => {
+                match join(
+                    &rooms,
+                    &mut outbox,
+                    connection_id,
+                    &socket,
+                    room,
+                    player,
+                    seen,
+                ) {
+                    Ok(seat) => joined = Some(seat),
+                    Err(message) => {
+                        let _ignored = socket.send(&RelayMessage::Error { message }.encode());
+                        break;
+                    }
+                }
+            }
+            (Ok(message), Some((room, seat))) if message.is_forwarded() => {
+                forward(&rooms, &mut outbox, room, *seat, connection_id, text)
+            }
+            _ => {
+                let _ignored = socket.send(
+                    &RelayMessage::Error {
+                        message: "Unexpected message".to_string(),
+                    }
+                    .encode(),
+                );
+            }
+        }
+        outbox.send();
+    }
+    if let Some((room, seat)) = joined {
+        let mut outbox = Outbox::default();
+        leave(&rooms, &mut outbox, &room, seat, connection_id);
+        outbox.send();
+    }
+}
+
+fn main() {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    let listener = TcpListener::bind(&address).expect("Could not listen on the address");
+    println!("Relay listening on ws://{}", address);
+    let rooms: Rooms = Arc::new(Mutex::new(HashMap::new()));
+    for (connection_id, stream) in listener.incoming().enumerate() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        let rooms = rooms.clone();
+        thread::spawn(move || {
+            if let Ok(socket) = WebSocket::accept(stream) {
+                handle(rooms, connection_id, socket);
+            }
+        });
+    }
+}
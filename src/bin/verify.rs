@@ -0,0 +1,70 @@
+// Checks a set of clues for a puzzle: how many secrets fit them, what they are, and which clues could be left out.
+// Run with `cargo run --bin verify [--symbols=0123456789] [--multiset] < clues.txt`, where the clues are one guess and its outcome per line, like "123 fp".
+// Secrets are as long as the first guess. Exits with 0 only if exactly one secret fits.
+use pbfweb::pbf::session::{parse_alphabet, verifier_for, DEFAULT_SYMBOLS};
+use pbfweb::pbf::Scoring;
+use std::io::Read;
+use std::process::exit;
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    exit(2);
+}
+
+fn main() {
+    let mut symbols = parse_alphabet(DEFAULT_SYMBOLS).unwrap_or_else(|e| fail(&e));
+    let mut scoring = Scoring::Set;
+    for arg in std::env::args().skip(1) {
+        if let Some(value) = arg.strip_prefix("--symbols=") {
+            symbols = parse_alphabet(value).unwrap_or_else(|e| fail(&e));
+        } else if arg == "--multiset" {
+            scoring = Scoring::Multiset;
+        } else {
+            fail(&format!("Unknown argument {}", arg));
+        }
+    }
+
+    let mut text = String::new();
+    if std::io::stdin().read_to_string(&mut text).is_err() {
+        fail("Could not read the clues");
+    }
+    let (solver, clues) = verifier_for(&text, &symbols, scoring).unwrap_or_else(|e| fail(&e));
+    let verification = solver.verify(&clues).unwrap_or_else(|e| fail(&e));
+
+    match verification.solution_count {
+        0 => println!("No secret fits the clues."),
+        1 => println!("Exactly one secret fits the clues:"),
+        count => println!("{} secrets fit the clues:", count),
+    }
+    let mut solutions: Vec<String> = verification
+        .solutions
+        .iter()
+        .map(|solution| solution.iter().collect())
+        .collect();
+    solutions.sort();
+    for solution in &solutions {
+        println!("  {}", solution);
+    }
+    if verification.solutions.len() < verification.solution_count {
+        println!(
+            "  and {} more",
+            verification.solution_count - verification.solutions.len()
+        );
+    }
+    for (i, clue) in clues.iter().enumerate() {
+        let needed = if verification.necessary.contains(&i) {
+            "needed"
+        } else {
+            "redundant"
+        };
+        println!(
+            "{} {}: {}",
+            clue.guess.iter().collect::<String>(),
+            clue.result,
+            needed
+        );
+    }
+    if verification.solution_count != 1 {
+        exit(1);
+    }
+}
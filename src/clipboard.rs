@@ -0,0 +1,13 @@
+use core::future::Future;
+use futures::FutureExt;
+
+// Fails if the browser refuses, e.g. because the page isn't served over https.
+pub fn copy_to_clipboard(text: &str) -> impl Future<Output = Result<(), ()>> {
+    let promise = web_sys::window()
+        .expect("Need window feature enabled")
+        .navigator()
+        .clipboard()
+        .write_text(text);
+    wasm_bindgen_futures::JsFuture::from(promise)
+        .map(|result| result.map(|_ignored| ()).map_err(|_ignored| ()))
+}
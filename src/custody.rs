@@ -0,0 +1,281 @@
+// Talks to the custody server from src/bin/custody.rs, which keeps the secrets and scores every guess so they never reach the guesser's browser.
+// Requests and responses are plain text in the same "key=value" lines as share links.
+use crate::game::{GameStatus, ScoredGuess};
+use crate::pbf::PositionResult;
+use crate::rules::{split_field, Rules};
+use crate::versus::random_player_id;
+use crate::PbfStats;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{RequestInit, Response};
+use yew::format::Text;
+use yew::services::storage::{Area, StorageService};
+
+// Games are only kept by a server when the app is built with PBF_CUSTODY_URL set, e.g. to http://localhost:9002. Otherwise the secret is encrypted into the link as usual.
+pub fn custody_url() -> Option<&'static str> {
+    option_env!("PBF_CUSTODY_URL")
+}
+
+// Links to games kept by a custody server look like /?game=<id>.
+pub const GAME_QUERY_PREFIX: &str = "game=";
+
+// The game id from the url, if it links to a game kept by a custody server.
+pub fn linked_game() -> Option<String> {
+    web_sys::window()?
+        .location()
+        .search()
+        .ok()?
+        .strip_prefix('?')?
+        .strip_prefix(GAME_QUERY_PREFIX)
+        .map(|game| game.to_string())
+}
+
+const CLIENT_STORAGE_KEY: &str = "pbf.custody.client";
+
+// Identifies this browser to the custody server, which gives it back the same session whenever it comes back to a game.
+fn client_token() -> String {
+    let mut storage = StorageService::new(Area::Local).ok();
+    if let Some(token) = storage
+        .as_ref()
+        .and_then(|storage| storage.restore::<Text>(CLIENT_STORAGE_KEY).ok())
+    {
+        return token;
+    }
+    let token = random_player_id();
+    if let Some(storage) = &mut storage {
+        let value: Text = Ok(token.clone());
+        storage.store(CLIENT_STORAGE_KEY, value);
+    }
+    token
+}
+
+// Everything a guesser is told about a game before it's over, which is everything but the secrets.
+pub struct Session {
+    // Identifies this guesser's attempt, so the server can tell when it's over.
+    pub id: String,
+    pub secret_count: usize,
+    pub secret_length: usize,
+    pub rules: Rules,
+    pub status: GameStatus,
+    // Empty until the game is over.
+    pub secrets: Vec<String>,
+    // The guesses already made when coming back to a game, each as the server scored it. Only the last can have ended the game.
+    pub played: Vec<(String, ScoredGuess)>,
+}
+
+fn parse_number(value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_ignored| format!("{} is not a number", value))
+}
+
+fn decode_lines(s: &str) -> Result<Vec<(&str, &str)>, String> {
+    s.lines()
+        .map(|line| split_field(line).map_err(|_ignored| format!("Unexpected response: {}", line)))
+        .collect()
+}
+
+impl Session {
+    // Each guess already made starts with a "played=" line, followed by how it was scored.
+    pub fn encode(&self) -> String {
+        let mut encoded = format!(
+            "session={}\nsecrets={}\nlength={}\nstatus={}\n{}",
+            self.id,
+            self.secret_count,
+            self.secret_length,
+            self.status.name(),
+            self.rules.encode()
+        );
+        for secret in &self.secrets {
+            encoded.push_str(&format!("secret={}\n", secret));
+        }
+        for (guess, scored) in &self.played {
+            encoded.push_str(&format!("played={}\n{}", guess, encode_scored(scored)));
+        }
+        encoded
+    }
+
+    pub fn decode(s: &str) -> Result<Self, String> {
+        let mut session = Session {
+            id: String::new(),
+            secret_count: 0,
+            secret_length: 0,
+            rules: Rules::default(),
+            status: GameStatus::InProgress,
+            secrets: Vec::new(),
+            played: Vec::new(),
+        };
+        // The lines about the session itself, then each played guess with the lines scoring it.
+        let mut header = String::new();
+        let mut played: Vec<(&str, String)> = Vec::new();
+        for line in s.lines() {
+            let lines = match (line.strip_prefix("played="), played.last_mut()) {
+                (Some(guess), _) => {
+                    played.push((guess, String::new()));
+                    continue;
+                }
+                (None, Some((_, scored))) => scored,
+                (None, None) => &mut header,
+            };
+            lines.push_str(line);
+            lines.push('\n');
+        }
+        for (key, value) in decode_lines(&header)? {
+            match key {
+                "session" => session.id = value.to_string(),
+                "secrets" => session.secret_count = parse_number(value)?,
+                "length" => session.secret_length = parse_number(value)?,
+                "status" => {
+                    session.status = GameStatus::from_name(value)
+                        .map_err(|_ignored| format!("{} is not a game status", value))?
+                }
+                "secret" => session.secrets.push(value.to_string()),
+                _ => session
+                    .rules
+                    .decode_field(key, value)
+                    .map_err(|_ignored| format!("{} is not a valid {}", value, key))?,
+            }
+        }
+        for (guess, scored) in played {
+            session
+                .played
+                .push((guess.to_string(), decode_scored(&scored)?));
+        }
+        if session.id.is_empty() || session.secret_count == 0 {
+            return Err("The server didn't describe the game".to_string());
+        }
+        Ok(session)
+    }
+}
+
+// Positions are written with the game's own letters: f in the right place, p elsewhere in the secret, b absent.
+fn encode_positions(positions: &[PositionResult]) -> String {
+    positions
+        .iter()
+        .map(|position| match position {
+            PositionResult::Exact => 'f',
+            PositionResult::Present => 'p',
+            PositionResult::Absent => 'b',
+        })
+        .collect()
+}
+
+fn decode_positions(s: &str) -> Result<Vec<PositionResult>, String> {
+    s.chars()
+        .map(|c| match c {
+            'f' => Ok(PositionResult::Exact),
+            'p' => Ok(PositionResult::Present),
+            'b' => Ok(PositionResult::Absent),
+            _ => Err(format!("{} is not a position result", c)),
+        })
+        .collect()
+}
+
+// One "result=p,f" line per secret, then positions, then the status and, once the game is over, the secrets.
+pub fn encode_scored(scored: &ScoredGuess) -> String {
+    let mut encoded: String = scored
+        .results
+        .iter()
+        .map(|result| format!("result={},{}\n", result.p, result.f))
+        .collect();
+    for positions in scored.positions.iter().flatten() {
+        encoded.push_str(&format!("positions={}\n", encode_positions(positions)));
+    }
+    encoded.push_str(&format!("status={}\n", scored.status.name()));
+    for secret in &scored.secrets {
+        encoded.push_str(&format!("secret={}\n", secret));
+    }
+    encoded
+}
+
+pub fn decode_scored(s: &str) -> Result<ScoredGuess, String> {
+    let mut results = Vec::new();
+    let mut positions = Vec::new();
+    let mut status = None;
+    let mut secrets = Vec::new();
+    for (key, value) in decode_lines(s)? {
+        match key {
+            "result" => {
+                let (p, f) = value
+                    .split_once(',')
+                    .ok_or(format!("{} is not a result", value))?;
+                results.push(PbfStats {
+                    p: parse_number(p)? as i32,
+                    f: parse_number(f)? as i32,
+                });
+            }
+            "positions" => positions.push(decode_positions(value)?),
+            "status" => {
+                status = Some(
+                    GameStatus::from_name(value)
+                        .map_err(|_ignored| format!("{} is not a game status", value))?,
+                )
+            }
+            "secret" => secrets.push(value.to_string()),
+            _ => {}
+        }
+    }
+    Ok(ScoredGuess {
+        results,
+        positions: if positions.is_empty() {
+            None
+        } else {
+            Some(positions)
+        },
+        status: status.ok_or("The server didn't say how the game is going")?,
+        secrets,
+    })
+}
+
+// Errors come back as the text of the response.
+async fn post(path: String, body: String) -> Result<String, String> {
+    let url = format!(
+        "{}{}",
+        custody_url().ok_or("No custody server is configured")?,
+        path
+    );
+    let init = RequestInit::new();
+    init.set_method("POST");
+    init.set_body(&JsValue::from_str(&body));
+    let response = JsFuture::from(
+        web_sys::window()
+            .expect("Need window feature enabled")
+            .fetch_with_str_and_init(&url, &init),
+    )
+    .await
+    .map_err(|_ignored| "Couldn't reach the custody server".to_string())?;
+    let response: Response = response.dyn_into().map_err(|_ignored| "Not a response")?;
+    let text = match response.text() {
+        Ok(text) => JsFuture::from(text)
+            .await
+            .ok()
+            .and_then(|text| text.as_string()),
+        Err(_) => None,
+    }
+    .unwrap_or_default();
+    if response.ok() {
+        Ok(text)
+    } else {
+        Err(text)
+    }
+}
+
+// Hands the encoded SecretPayload to the server and returns the id of the new game.
+pub async fn create_game(payload: String) -> Result<String, String> {
+    post("/games".to_string(), payload)
+        .await
+        .map(|id| id.trim().to_string())
+}
+
+// Coming back to a game this browser has played before resumes that session rather than starting over.
+pub async fn start_session(game: String) -> Result<Session, String> {
+    Session::decode(&post(format!("/games/{}/sessions", game), client_token()).await?)
+}
+
+pub async fn guess(session: String, guess: String) -> Result<ScoredGuess, String> {
+    decode_scored(&post(format!("/sessions/{}/guesses", session), guess).await?)
+}
+
+pub async fn give_up(session: String) -> Result<ScoredGuess, String> {
+    decode_scored(&post(format!("/sessions/{}/give_up", session), String::new()).await?)
+}
@@ -0,0 +1,145 @@
+use crate::game::GuessState;
+use crate::rules::{Rules, SecretPayload};
+use yew::format::Text;
+use yew::services::storage::{Area, StorageService};
+
+// Changing this changes every daily secret, past and future.
+const DAILY_SEED: u64 = 0x5eed_b1e5_f00d_cafe;
+
+// Day (counted in days since the unix epoch) of the first daily puzzle.
+const FIRST_DAILY_DAY: i64 = 20744;
+
+const DAILY_SECRET_LENGTH: usize = 3;
+const DAILY_MAX_GUESSES: usize = 10;
+
+const DAY_STORAGE_KEY: &str = "pbf.daily.day";
+const GUESSES_STORAGE_KEY: &str = "pbf.daily.guesses";
+const HINTS_STORAGE_KEY: &str = "pbf.daily.hints";
+const STREAK_STORAGE_KEY: &str = "pbf.daily.streak";
+const LAST_WIN_STORAGE_KEY: &str = "pbf.daily.last_win";
+
+// The current UTC date, as a number of days since the unix epoch.
+pub fn today() -> i64 {
+    (js_sys::Date::now() / 86_400_000.0).floor() as i64
+}
+
+pub fn puzzle_number(day: i64) -> i64 {
+    day - FIRST_DAILY_DAY + 1
+}
+
+// SplitMix64, which is plenty random for picking digits and gives the same answer on every browser.
+pub fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Standard rules: distinct digits, drawn by shuffling the ten digits with a generator seeded from the day.
+pub fn daily_secret(day: i64) -> String {
+    let mut state = DAILY_SEED ^ day as u64;
+    let mut digits: Vec<char> = ('0'..='9').collect();
+    for i in (1..digits.len()).rev() {
+        let j = (next_random(&mut state) % (i as u64 + 1)) as usize;
+        digits.swap(i, j);
+    }
+    digits.into_iter().take(DAILY_SECRET_LENGTH).collect()
+}
+
+pub fn daily_game(day: i64) -> GuessState {
+    GuessState::new(SecretPayload {
+        secrets: vec![daily_secret(day)],
+        rules: Rules {
+            max_guesses: Some(DAILY_MAX_GUESSES),
+            ..Rules::default()
+        },
+    })
+}
+
+// The player's daily history, kept in local storage so each day can only be played once.
+pub struct DailyRecord {
+    storage: StorageService,
+}
+
+impl DailyRecord {
+    pub fn new() -> Option<Self> {
+        StorageService::new(Area::Local)
+            .ok()
+            .map(|storage| Self { storage })
+    }
+
+    fn restore(&self, key: &str) -> Option<String> {
+        self.storage.restore::<Text>(key).ok()
+    }
+
+    fn restore_day(&self, key: &str) -> Option<i64> {
+        self.restore(key).and_then(|day| day.parse().ok())
+    }
+
+    fn store(&mut self, key: &str, value: String) {
+        let value: Text = Ok(value);
+        self.storage.store(key, value);
+    }
+
+    // Guesses already made on the given day, so reloading the page resumes the same attempt instead of starting a new one.
+    pub fn saved_guesses(&self, day: i64) -> Vec<String> {
+        if self.restore_day(DAY_STORAGE_KEY) != Some(day) {
+            return Vec::new();
+        }
+        self.restore(GUESSES_STORAGE_KEY)
+            .map(|guesses| guesses.lines().map(|guess| guess.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    // How many hints were used on the given day, so the share card still shows them after a reload.
+    pub fn saved_hints(&self, day: i64) -> usize {
+        if self.restore_day(DAY_STORAGE_KEY) != Some(day) {
+            return 0;
+        }
+        self.restore(HINTS_STORAGE_KEY)
+            .and_then(|hints| hints.parse().ok())
+            .unwrap_or(0)
+    }
+
+    // Each guess is stored as entered and ends with a newline, so a list of guesses is never mistaken for one more or one fewer.
+    pub fn save_progress(&mut self, day: i64, guesses: &[String], hints_used: usize) {
+        self.store(DAY_STORAGE_KEY, day.to_string());
+        self.store(
+            GUESSES_STORAGE_KEY,
+            guesses.iter().map(|guess| format!("{}\n", guess)).collect(),
+        );
+        self.store(HINTS_STORAGE_KEY, hints_used.to_string());
+    }
+
+    // Called once when the day's game ends.
+    pub fn record_result(&mut self, day: i64, won: bool) {
+        let streak = if won {
+            if self.restore_day(LAST_WIN_STORAGE_KEY) == Some(day - 1) {
+                self.stored_streak() + 1
+            } else {
+                1
+            }
+        } else {
+            0
+        };
+        if won {
+            self.store(LAST_WIN_STORAGE_KEY, day.to_string());
+        }
+        self.store(STREAK_STORAGE_KEY, streak.to_string());
+    }
+
+    fn stored_streak(&self) -> u32 {
+        self.restore(STREAK_STORAGE_KEY)
+            .and_then(|streak| streak.parse().ok())
+            .unwrap_or(0)
+    }
+
+    // Number of consecutive days won. A streak is broken as soon as a day is missed, even before the next win is recorded.
+    pub fn streak(&self, today: i64) -> u32 {
+        match self.restore_day(LAST_WIN_STORAGE_KEY) {
+            Some(last_win) if last_win >= today - 1 => self.stored_streak(),
+            _ => 0,
+        }
+    }
+}
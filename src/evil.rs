@@ -0,0 +1,216 @@
+use crate::game::{GameStatus, GuessSpace};
+use crate::pbf::solver::{can_enumerate, GuessState};
+use crate::pbf::{Feedback, Scoring};
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::{html, Component, ComponentLink, Html, NodeRef};
+
+const DEFAULT_LENGTH: usize = 3;
+
+// The end of the game lists what the secret could still have been, up to this many.
+const MAX_REVEALED: usize = 100;
+
+fn new_solver(length: usize) -> GuessState<GuessSpace> {
+    GuessState::with_rules(
+        ('0'..='9').collect(),
+        length,
+        Scoring::Set,
+        Feedback::Counts,
+    )
+}
+
+// A codemaker that never picks a secret. It keeps every secret that fits its answers so far, and answers each guess with whatever keeps the most of them, so the only way to win is to leave it one secret and then guess that.
+pub struct EvilComponent {
+    link: ComponentLink<Self>,
+    solver: GuessState<GuessSpace>,
+    // How many secrets were still possible after each guess.
+    candidates_after: Vec<usize>,
+    status: GameStatus,
+    error: Option<String>,
+    guess_ref: NodeRef,
+    length_ref: NodeRef,
+}
+
+pub enum Msg {
+    Guess,
+    GiveUp,
+    NewGame,
+}
+
+impl EvilComponent {
+    fn guess(&mut self) -> Result<(), String> {
+        let guess: Vec<GuessSpace> = self
+            .guess_ref
+            .cast::<HtmlInputElement>()
+            .unwrap()
+            .value()
+            .trim()
+            .chars()
+            .collect();
+        let length = self.solver.guess_length();
+        if guess.len() != length || !guess.iter().all(char::is_ascii_digit) {
+            return Err(format!("Guesses must be {} digits", length));
+        }
+        let scored = self
+            .solver
+            .evil_score(guess)
+            .ok_or("There's nothing left for the secret to be")?;
+        let won = scored.result.f as usize == length;
+        self.solver.add_guess(scored);
+        self.candidates_after.push(self.solver.candidate_count());
+        if won {
+            self.status = GameStatus::Won;
+        }
+        Ok(())
+    }
+
+    fn new_game(&mut self) -> Result<(), String> {
+        let length = match self
+            .length_ref
+            .cast::<HtmlSelectElement>()
+            .unwrap()
+            .value()
+            .parse()
+        {
+            Ok(length) if can_enumerate(10, length) => length,
+            _ => return Err("Pick a length".to_string()),
+        };
+        self.solver = new_solver(length);
+        self.candidates_after.clear();
+        self.status = GameStatus::InProgress;
+        Ok(())
+    }
+
+    fn render_guesses(&self) -> Html {
+        html! {
+            <ul>
+                {for self.solver.guesses().iter().zip(&self.candidates_after).map(|(guess, left)| html! {
+                    <li>
+                        {guess.guess.iter().collect::<String>()}{" - "}{guess.result}
+                        <span class="candidates">{format!(" ({} left)", left)}</span>
+                    </li>
+                })}
+            </ul>
+        }
+    }
+
+    // What the secret could have been, which is the only secret there ever was once the guesser has won.
+    fn render_reveal(&self) -> Html {
+        let mut candidates: Vec<String> = self
+            .solver
+            .candidates()
+            .take(MAX_REVEALED)
+            .map(|candidate| candidate.into_iter().collect())
+            .collect();
+        candidates.sort();
+        let count = self.solver.candidate_count();
+        let summary = match self.status {
+            GameStatus::Won => format!(
+                "You cornered it in {} guesses. It had nothing left to be but {}.",
+                self.solver.guesses().len(),
+                candidates.join("")
+            ),
+            _ if count == 1 => format!(
+                "It had nothing left to be but {}. All that was left was to guess it.",
+                candidates.join("")
+            ),
+            _ => format!(
+                "It never picked a secret. It could still have been any of these {}:",
+                count
+            ),
+        };
+        let more_html = if count > candidates.len() {
+            html! {<p>{format!("and {} more", count - candidates.len())}</p>}
+        } else {
+            html! {}
+        };
+        html! {
+            <div>
+                <p class="reveal">{summary}</p>
+                {
+                    if count > 1 {
+                        html! {
+                            <>
+                            <ul class="solutions">
+                                {for candidates.iter().map(|candidate| html! {<li>{candidate}</li>})}
+                            </ul>
+                            {more_html}
+                            </>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+
+    fn render_play(&self) -> Html {
+        if self.status != GameStatus::InProgress {
+            return self.render_reveal();
+        }
+        html! {
+            <div>
+                <p>{format!("{} secrets are still possible.", self.solver.candidate_count())}</p>
+                <label for="evil_guess">{"Next guess"}</label>
+                <input type="text" id="evil_guess" ref=self.guess_ref.clone()/>
+                <input type="submit" value="Guess" onclick=self.link.callback(|_| Msg::Guess)/>
+                <input type="submit" value="Give up" onclick=self.link.callback(|_| Msg::GiveUp)/>
+            </div>
+        }
+    }
+}
+
+impl Component for EvilComponent {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            link,
+            solver: new_solver(DEFAULT_LENGTH),
+            candidates_after: Vec::new(),
+            status: GameStatus::InProgress,
+            error: None,
+            guess_ref: NodeRef::default(),
+            length_ref: NodeRef::default(),
+        }
+    }
+    fn update(&mut self, msg: Self::Message) -> bool {
+        let result = match msg {
+            Msg::Guess => self.guess(),
+            Msg::GiveUp => {
+                self.status = GameStatus::GaveUp;
+                Ok(())
+            }
+            Msg::NewGame => self.new_game(),
+        };
+        self.error = result.err();
+        true
+    }
+    fn change(&mut self, _: Self::Properties) -> bool {
+        false
+    }
+    fn view(&self) -> Html {
+        let length = self.solver.guess_length();
+        html! {
+            <div>
+                <h1>{"Evil codemaker"}</h1>
+                <p>{"This codemaker cheats. It never picks a secret, and answers every guess with whatever result keeps the most secrets possible. To win, pin it down to a single secret and then guess it."}</p>
+                <p>{format!("The secret is {} digits, which may repeat.", length)}</p>
+                {self.render_guesses()}
+                {self.render_play()}
+                <span class="error">{self.error.as_ref().unwrap_or(&"".to_string())}</span>
+                <div>
+                    <label for="evil_length">{"Digits"}</label>
+                    <select id="evil_length" ref=self.length_ref.clone()>
+                        {for (3..=5).map(|option| html! {
+                            <option value=option.to_string() selected=option == length>{option}</option>
+                        })}
+                    </select>
+                    <input type="submit" value="New game" onclick=self.link.callback(|_| Msg::NewGame)/>
+                </div>
+                <p><a href="/">{"Back to the main game"}</a></p>
+            </div>
+        }
+    }
+}
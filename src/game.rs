@@ -0,0 +1,650 @@
+use crate::pbf::analysis::{analyze_game, GuessAnalysis};
+use crate::pbf::solver::{
+    can_enumerate, can_pack, Guess, GuessState as SolverState, KnowledgeGrid, MultiGuessState,
+    MAX_SCORED_CANDIDATES, MAX_SCORED_GUESSES,
+};
+use crate::pbf::{Feedback, PbfStats, PositionResult};
+use crate::rules::{Consistency, Rules, SecretPayload, Variant};
+use crate::secret::Secret;
+use crate::words::{dictionary, is_word};
+use std::collections::HashMap;
+
+pub type GuessSpace = char;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GameStatus {
+    InProgress,
+    Won,
+    // Ran out of guesses.
+    Lost,
+    GaveUp,
+}
+
+impl GameStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            GameStatus::InProgress => "in_progress",
+            GameStatus::Won => "won",
+            GameStatus::Lost => "lost",
+            GameStatus::GaveUp => "gave_up",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, ()> {
+        match name {
+            "in_progress" => Ok(GameStatus::InProgress),
+            "won" => Ok(GameStatus::Won),
+            "lost" => Ok(GameStatus::Lost),
+            "gave_up" => Ok(GameStatus::GaveUp),
+            _ => Err(()),
+        }
+    }
+}
+
+// What a custody server said about a guess, for games whose secrets this side doesn't know.
+#[derive(Clone)]
+pub struct ScoredGuess {
+    // One per secret.
+    pub results: Vec<PbfStats>,
+    // Only when playing with positional feedback.
+    pub positions: Option<Vec<Vec<PositionResult>>>,
+    pub status: GameStatus,
+    // Empty until the game is over.
+    pub secrets: Vec<String>,
+}
+
+pub struct GuessState {
+    // Map from character to position. There's one per secret being guessed at the same time, all of the same length.
+    // Empty until the game is over when the secrets are kept by a custody server.
+    pub secrets: Vec<Secret<GuessSpace>>,
+    pub secret_count: usize,
+    pub secret_length: usize,
+    pub rules: Rules,
+    pub guesses: Vec<String>,
+    // How each guess compared with the solver's. Filled in once the game is over.
+    pub analysis: Option<Vec<GuessAnalysis<GuessSpace>>>,
+    status: GameStatus,
+    // Results from the custody server, by guess. Only used while the secrets aren't known here.
+    scored: HashMap<String, ScoredGuess>,
+    // How many times the guesser asked the solver for help.
+    pub hints_used: usize,
+}
+
+// Grading a finished game replays it with the solver. Its best guess is scored on a sample of at most MAX_SCORED_GUESSES by MAX_SCORED_CANDIDATES whatever the size of the game, but every guess also filters all the candidates, so past as many candidates as that sample holds the filtering costs more than the scoring and games that big aren't graded.
+const MAX_ANALYSIS_CANDIDATES: usize = MAX_SCORED_GUESSES * MAX_SCORED_CANDIDATES;
+
+// Hints give away more the further up the ladder they are: how many secrets are still possible, then one symbol the solver is sure about, then the guess it would make.
+pub const MAX_HINT_LEVEL: usize = 3;
+
+// What the solver makes of the game so far, as much as the hint's level gives away.
+pub struct Hint {
+    // From 1 to MAX_HINT_LEVEL.
+    pub level: usize,
+    // How many values each secret could still have.
+    pub candidates: Vec<usize>,
+    // Something certain about one of the secrets, from level 2, if the solver has found anything the guesser can't already see.
+    pub clue: Option<String>,
+    // The guess the solver would make next, at the last level.
+    pub suggestion: Option<String>,
+}
+
+impl GuessState {
+    pub fn new(payload: SecretPayload) -> Self {
+        Self {
+            secret_length: payload
+                .secrets
+                .first()
+                .map(|secret| secret.chars().count())
+                .unwrap_or(0),
+            secrets: payload
+                .secrets
+                .iter()
+                .map(|secret| Secret::new(secret.chars().collect()))
+                .collect(),
+            secret_count: payload.secrets.len(),
+            rules: payload.rules,
+            guesses: Vec::new(),
+            analysis: None,
+            status: GameStatus::InProgress,
+            scored: HashMap::new(),
+            hints_used: 0,
+        }
+    }
+
+    // A game whose secrets are kept by a custody server, which scores every guess.
+    pub fn held_by_server(secret_count: usize, secret_length: usize, rules: Rules) -> Self {
+        Self {
+            secrets: Vec::new(),
+            secret_count,
+            secret_length,
+            rules,
+            guesses: Vec::new(),
+            analysis: None,
+            status: GameStatus::InProgress,
+            scored: HashMap::new(),
+            hints_used: 0,
+        }
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.status != GameStatus::InProgress
+    }
+
+    // How many more guesses can be made, if the rules limit them.
+    pub fn guesses_left(&self) -> Option<usize> {
+        self.rules
+            .max_guesses
+            .map(|max_guesses| max_guesses.saturating_sub(self.guesses.len()))
+    }
+
+    // One result per secret.
+    pub fn compare(&self, guess: &str) -> Vec<PbfStats> {
+        if let Some(scored) = self.scored.get(guess) {
+            return scored.results.clone();
+        }
+        if self.rules.feedback == Feedback::Positions {
+            return self
+                .compare_positions(guess)
+                .iter()
+                .map(|positions| PbfStats::from_positions(positions))
+                .collect();
+        }
+        self.secrets
+            .iter()
+            .map(|secret| secret.compare_with(guess.chars(), self.rules.variant.scoring()))
+            .collect()
+    }
+
+    // One result per position of the guess, for each secret.
+    pub fn compare_positions(&self, guess: &str) -> Vec<Vec<PositionResult>> {
+        if let Some(positions) = self
+            .scored
+            .get(guess)
+            .and_then(|scored| scored.positions.clone())
+        {
+            return positions;
+        }
+        self.secrets
+            .iter()
+            .map(|secret| secret.compare_positions(guess.chars()))
+            .collect()
+    }
+
+    // Checks the guess is something that may be guessed, before it's scored.
+    pub fn validate_guess(&self, guess: &str) -> Result<(), String> {
+        if guess.chars().count() != self.secret_length {
+            return Err(format!(
+                "Guesses must be {} symbols long",
+                self.secret_length
+            ));
+        }
+        if let Variant::Words = self.rules.variant {
+            if !is_word(guess) {
+                return Err(format!("{} is not in the word list", guess));
+            }
+        }
+        if self.rules.consistency == Consistency::Hard {
+            if let Some(contradiction) = self.contradiction(guess) {
+                return Err(contradiction);
+            }
+        }
+        Ok(())
+    }
+
+    // What to tell the guesser about a guess that's allowed but can't be the secret, when the rules ask for that.
+    pub fn guess_warning(&self, guess: &str) -> Option<String> {
+        match self.rules.consistency {
+            Consistency::Soft => self.contradiction(guess),
+            Consistency::Unchecked | Consistency::Hard => None,
+        }
+    }
+
+    // Why the guess can't be any of the secrets still to find, given the results so far, if it can't.
+    pub fn contradiction(&self, guess: &str) -> Option<String> {
+        // Only the symbols that have been played matter, since the results say nothing about any others.
+        let mut symbols: Vec<GuessSpace> = self
+            .guesses
+            .iter()
+            .flat_map(|guess| guess.chars())
+            .chain(guess.chars())
+            .collect();
+        symbols.sort_unstable();
+        symbols.dedup();
+        if guess.chars().count() != self.secret_length
+            || !can_pack(symbols.len(), self.secret_length)
+        {
+            return None;
+        }
+        let mut states: Vec<SolverState<GuessSpace>> = (0..self.secret_count)
+            .map(|_| {
+                SolverState::without_candidates(
+                    symbols.clone(),
+                    self.secret_length,
+                    self.rules.variant.scoring(),
+                    self.rules.feedback,
+                )
+            })
+            .collect();
+        for earlier in &self.guesses {
+            for (state, scored) in states.iter_mut().zip(self.solver_guesses(earlier)) {
+                state.add_guess(scored);
+            }
+        }
+        let candidate: Vec<GuessSpace> = guess.chars().collect();
+        let mut contradictions = states
+            .iter()
+            .zip(self.solved_secrets())
+            .filter(|(_, solved)| !solved)
+            .map(|(state, _)| state.contradiction(&candidate));
+        if self.secret_count > 1 {
+            return if contradictions.all(|contradiction| contradiction.is_some()) {
+                Some(format!(
+                    "{} can't be any of the secrets still to find",
+                    guess
+                ))
+            } else {
+                None
+            };
+        }
+        let earlier = contradictions.next()??;
+        let earlier_guess: String = earlier.guess.iter().collect();
+        Some(match &earlier.positions {
+            Some(_) => format!(
+                "{} can't be the secret, because of which positions matched in {}",
+                guess, earlier_guess
+            ),
+            None => format!(
+                "{} can't be the secret, because {} got {}",
+                guess,
+                earlier_guess,
+                self.format_result(&earlier.result)
+            ),
+        })
+    }
+
+    // Writes a result in the notation of the variant being played.
+    pub fn format_result(&self, result: &PbfStats) -> String {
+        match self.rules.variant {
+            Variant::BullsAndCows => result.bulls_and_cows(),
+            Variant::PicoBagelFermi | Variant::Mastermind | Variant::Words => result.to_string(),
+        }
+    }
+
+    pub fn is_correct(&self, result: &PbfStats) -> bool {
+        result.f == self.secret_length as i32
+    }
+
+    // Whether each secret has been hit by one of the guesses so far.
+    pub fn solved_secrets(&self) -> Vec<bool> {
+        let mut solved = vec![false; self.secret_count];
+        for guess in &self.guesses {
+            for (solved, result) in solved.iter_mut().zip(self.compare(guess)) {
+                *solved |= self.is_correct(&result);
+            }
+        }
+        solved
+    }
+
+    pub fn guess(&mut self, guess: String) {
+        if self.is_over() {
+            panic!("Guessing after the game is over");
+        }
+        self.guesses.push(guess);
+        if self.solved_secrets().iter().all(|solved| *solved) {
+            self.status = GameStatus::Won;
+        } else if self.guesses_left() == Some(0) {
+            self.status = GameStatus::Lost;
+        }
+    }
+
+    // Records a guess the custody server has scored. Its word on whether the game is over is final.
+    pub fn guess_scored(&mut self, guess: String, scored: ScoredGuess) {
+        self.status = scored.status;
+        self.reveal(&scored.secrets);
+        self.scored.insert(guess.clone(), scored);
+        self.guesses.push(guess);
+    }
+
+    // Fills in the secrets once the custody server has given them up.
+    pub fn reveal(&mut self, secrets: &[String]) {
+        if !secrets.is_empty() {
+            self.secrets = secrets
+                .iter()
+                .map(|secret| Secret::new(secret.chars().collect()))
+                .collect();
+        }
+    }
+
+    pub fn give_up(&mut self) {
+        if !self.is_over() {
+            self.status = GameStatus::GaveUp;
+        }
+    }
+
+    // A spoiler-free summary of the game: how many guesses it took and the outcome of each, without the guesses themselves.
+    pub fn share_text(&self, title: &str) -> String {
+        let limit = match self.rules.max_guesses {
+            Some(max_guesses) => format!("/{}", max_guesses),
+            None => "".to_string(),
+        };
+        let score = match self.status {
+            GameStatus::Won => format!("{}{}", self.guesses.len(), limit),
+            _ => format!("X{}", limit),
+        };
+        let hints = match self.hints_used {
+            0 => "".to_string(),
+            1 => " (1 hint)".to_string(),
+            hints_used => format!(" ({} hints)", hints_used),
+        };
+        let mut text = format!("{} {}{}\n", title, score, hints);
+        for guess in &self.guesses {
+            if self.rules.feedback == Feedback::Positions {
+                let row: Vec<String> = self
+                    .compare_positions(guess)
+                    .iter()
+                    .map(|positions| {
+                        positions
+                            .iter()
+                            .map(|position| match position {
+                                PositionResult::Exact => "\u{1F7E9}",
+                                PositionResult::Present => "\u{1F7E8}",
+                                PositionResult::Absent => "\u{2B1B}",
+                            })
+                            .collect()
+                    })
+                    .collect();
+                text.push_str(&row.join(" "));
+                text.push('\n');
+                continue;
+            }
+            let row: Vec<String> = self
+                .compare(guess)
+                .iter()
+                .map(|result| {
+                    let f = result.f as usize;
+                    let p = result.p as usize;
+                    let mut squares = "\u{1F7E9}".repeat(f);
+                    squares.push_str(&"\u{1F7E8}".repeat(p));
+                    squares.push_str(&"\u{2B1B}".repeat(self.secret_length.saturating_sub(f + p)));
+                    squares
+                })
+                .collect();
+            text.push_str(&row.join(" "));
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn secret_strings(&self) -> Vec<String> {
+        self.secrets
+            .iter()
+            .map(|secret| secret.as_guess().iter().collect())
+            .collect()
+    }
+
+    pub fn secret_string(&self) -> String {
+        self.secret_strings().join(" ")
+    }
+
+    // A solver for one of the secrets, starting from everything it could have been before any guesses. Variants with a fixed dictionary or set of symbols use that. Otherwise games played entirely with digits use all ten digits, and anything else is limited to the symbols that were actually played.
+    // None if there are too many possible secrets to enumerate.
+    pub fn solver_state(&self) -> Option<SolverState<GuessSpace>> {
+        self.solver_state_over(true)
+    }
+
+    // Only counts the secrets' own symbols as played when including them can't give the secrets away, i.e. once the game is over.
+    fn solver_state_over(&self, include_secrets: bool) -> Option<SolverState<GuessSpace>> {
+        let scoring = self.rules.variant.scoring();
+        let feedback = self.rules.feedback;
+        if let Variant::Words = self.rules.variant {
+            return dictionary(self.secret_length).map(|words| {
+                SolverState::from_dictionary(
+                    words.iter().map(|word| word.chars().collect()).collect(),
+                    scoring,
+                    feedback,
+                )
+            });
+        }
+        let secrets: &[Secret<GuessSpace>] = if include_secrets { &self.secrets } else { &[] };
+        let mut symbols = match self.rules.variant.guess_space() {
+            Some(guess_space) => guess_space,
+            None => secrets
+                .iter()
+                .flat_map(|secret| secret.as_guess().iter().cloned())
+                .chain(self.guesses.iter().flat_map(|guess| guess.chars()))
+                .collect(),
+        };
+        if symbols.iter().all(|c| c.is_ascii_digit()) {
+            symbols = ('0'..='9').collect();
+        }
+        symbols.sort_unstable();
+        symbols.dedup();
+        if can_enumerate(symbols.len(), self.secret_length) {
+            Some(SolverState::with_rules(
+                symbols,
+                self.secret_length,
+                scoring,
+                feedback,
+            ))
+        } else {
+            None
+        }
+    }
+
+    // A guess with the results it got, as the solver takes them. One per secret.
+    fn solver_guesses(&self, guess: &str) -> Vec<Guess<GuessSpace>> {
+        let positions: Vec<Option<Vec<PositionResult>>> =
+            if self.rules.feedback == Feedback::Positions {
+                self.compare_positions(guess)
+                    .into_iter()
+                    .map(Some)
+                    .collect()
+            } else {
+                vec![None; self.secret_count]
+            };
+        self.compare(guess)
+            .into_iter()
+            .zip(positions)
+            .map(|(result, positions)| Guess {
+                guess: guess.chars().collect(),
+                result,
+                positions,
+            })
+            .collect()
+    }
+
+    // Whether the guesser can know every symbol the secrets might use: the variant fixes them, or the game is played with digits.
+    // Otherwise the solver only knows the symbols guessed so far, and counting what's left among those would mislead.
+    fn symbols_known(&self) -> bool {
+        self.rules.variant.guess_space().is_some()
+            || self
+                .guesses
+                .iter()
+                .all(|guess| guess.chars().all(|c| c.is_ascii_digit()))
+    }
+
+    // A solver that knows only what the guesser has been told.
+    fn guesser_solver(&self) -> Result<MultiGuessState<GuessSpace>, String> {
+        if !self.symbols_known() {
+            return Err(
+                "The solver can't help when the secret can use any symbols, since it doesn't know which ones are left"
+                    .to_string(),
+            );
+        }
+        let states: Option<Vec<SolverState<GuessSpace>>> = (0..self.secret_count)
+            .map(|_| self.solver_state_over(false))
+            .collect();
+        let mut solver = MultiGuessState::new(
+            states.ok_or("There are too many possible secrets to give a hint")?,
+        );
+        for guess in &self.guesses {
+            solver.add_guess(self.solver_guesses(guess));
+        }
+        // Games that have only been played with digits so far are solved as digit games, which the secret may not be.
+        if solver
+            .states()
+            .iter()
+            .any(|state| state.candidate_count() == 0)
+        {
+            return Err(
+                "The solver doesn't know enough about which symbols the secret uses yet"
+                    .to_string(),
+            );
+        }
+        Ok(solver)
+    }
+
+    // Where each symbol can still go in each secret, from the solver.
+    pub fn knowledge(&self) -> Result<Vec<KnowledgeGrid<GuessSpace>>, String> {
+        Ok(self
+            .guesser_solver()?
+            .states()
+            .iter()
+            .map(|state| state.knowledge_grid())
+            .collect())
+    }
+
+    // Asks the solver about the game so far and counts it as a hint used.
+    pub fn take_hint(&mut self, level: usize) -> Result<Hint, String> {
+        let solver = self.guesser_solver()?;
+        let candidates: Vec<usize> = solver
+            .states()
+            .iter()
+            .map(|state| state.candidate_count())
+            .collect();
+        self.hints_used += 1;
+        Ok(Hint {
+            level,
+            candidates,
+            clue: if level >= 2 { self.clue(&solver) } else { None },
+            suggestion: if level >= MAX_HINT_LEVEL {
+                solver
+                    .next_guess()
+                    .map(|suggestion| suggestion.into_iter().collect())
+            } else {
+                None
+            },
+        })
+    }
+
+    // A position the solver has pinned down, or else a symbol it has ruled out, for the first secret not yet found.
+    // Skips what the results already show outright: symbols from guesses that got nothing, and positional feedback's exact matches.
+    fn clue(&self, solver: &MultiGuessState<GuessSpace>) -> Option<String> {
+        let (index, state) = solver
+            .states()
+            .iter()
+            .enumerate()
+            .find(|(_, state)| !state.is_solved())?;
+        let prefix = if self.secret_count > 1 {
+            format!("Secret {}: ", index + 1)
+        } else {
+            "".to_string()
+        };
+        let mut shown_absent: Vec<GuessSpace> = Vec::new();
+        let mut shown_exact: Vec<(usize, GuessSpace)> = Vec::new();
+        for guess in &self.guesses {
+            let result = self.compare(guess)[index];
+            if result.p == 0 && result.f == 0 {
+                shown_absent.extend(guess.chars());
+            }
+            if self.rules.feedback == Feedback::Positions {
+                let positions = &self.compare_positions(guess)[index];
+                for (position, (symbol, outcome)) in guess.chars().zip(positions).enumerate() {
+                    match outcome {
+                        PositionResult::Exact => shown_exact.push((position, symbol)),
+                        // Unless the same symbol was found elsewhere in the guess, absent means absent.
+                        PositionResult::Absent
+                            if guess.chars().zip(positions).all(|(other, outcome)| {
+                                other != symbol || *outcome == PositionResult::Absent
+                            }) =>
+                        {
+                            shown_absent.push(symbol)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        let grid = state.knowledge_grid();
+        if let Some((position, symbol)) = grid
+            .known_positions()
+            .into_iter()
+            .find(|known| !shown_exact.contains(known))
+        {
+            return Some(format!(
+                "{}The secret has {} in position {}",
+                prefix,
+                symbol,
+                position + 1
+            ));
+        }
+        // Symbols that were guessed are the ones worth knowing about.
+        let mut absent = grid.absent_symbols();
+        absent.retain(|symbol| !shown_absent.contains(symbol));
+        absent.sort_by_key(|symbol| !self.guesses.iter().any(|guess| guess.contains(*symbol)));
+        absent
+            .first()
+            .map(|symbol| format!("{}{} isn't anywhere in the secret", prefix, symbol))
+    }
+
+    pub fn analyze(&self) -> Option<Vec<GuessAnalysis<GuessSpace>>> {
+        let states: Option<Vec<SolverState<GuessSpace>>> =
+            self.secrets.iter().map(|_| self.solver_state()).collect();
+        let states = states.filter(|states| {
+            states
+                .iter()
+                .all(|state| state.candidate_count() <= MAX_ANALYSIS_CANDIDATES)
+        });
+        states.map(|states| {
+            let guesses: Vec<Vec<GuessSpace>> = self
+                .guesses
+                .iter()
+                .map(|guess| guess.chars().collect())
+                .collect();
+            analyze_game(&self.secrets, &guesses, MultiGuessState::new(states))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished_game(variant: Variant, secret: &str, guesses: &[&str]) -> GuessState {
+        let mut state = GuessState::new(SecretPayload {
+            secrets: vec![secret.to_string()],
+            rules: Rules {
+                variant,
+                ..Rules::default()
+            },
+        });
+        for guess in guesses {
+            state.guess(guess.to_string());
+        }
+        assert_eq!(state.status(), GameStatus::Won);
+        state
+    }
+
+    #[test]
+    fn four_digit_games_are_graded() {
+        for variant in [Variant::PicoBagelFermi, Variant::BullsAndCows] {
+            let state = finished_game(variant, "4271", &["1234", "5678", "4217", "4271"]);
+            let analysis = state.analyze().expect("a four digit game should be graded");
+            assert_eq!(analysis.len(), 4);
+            assert!(analysis[0].candidates_before[0] > 2_000);
+            assert_eq!(analysis[3].candidates_after, vec![1]);
+            assert!(analysis.iter().all(|guess| guess.best_guess.is_some()));
+        }
+    }
+
+    #[test]
+    fn games_too_big_to_grade_arent() {
+        let state = finished_game(Variant::PicoBagelFermi, "4271635", &["4271635"]);
+        assert!(state.solver_state().unwrap().candidate_count() > MAX_ANALYSIS_CANDIDATES);
+        assert!(state.analyze().is_none());
+    }
+}
@@ -0,0 +1,367 @@
+use crate::lobby::{LeaderboardEntry, LobbyMessage, PlayerGuesses};
+use crate::server_link::ServerLink;
+use crate::versus::random_player_id;
+use crate::PbfStats;
+use web_sys::HtmlInputElement;
+use yew::format::Text;
+use yew::services::websocket::WebSocketStatus;
+use yew::{html, Component, ComponentLink, Html, NodeRef};
+
+// Where the lobby server from src/bin/lobby.rs runs. Set PBF_LOBBY_URL when building to use another one.
+const DEFAULT_LOBBY_URL: &str = "ws://localhost:9003";
+
+fn get_value(node_ref: &NodeRef) -> String {
+    node_ref.cast::<HtmlInputElement>().unwrap().value()
+}
+
+enum Role {
+    Host { secret: String },
+    Guesser { nickname: String },
+}
+
+// What the finished message tells everyone.
+struct Finished {
+    winner: String,
+    secret: String,
+    guesses: Vec<PlayerGuesses>,
+}
+
+// One host's or guesser's view of a lobby, which carries on across reconnects.
+struct Session {
+    lobby: String,
+    player: String,
+    role: Role,
+
+    // Known once the server has let us in.
+    length: Option<usize>,
+    // Our own guesses, as scored by the server.
+    guesses: Vec<(String, PbfStats)>,
+    leaderboard: Vec<LeaderboardEntry>,
+    finished: Option<Finished>,
+}
+
+impl Session {
+    fn join_message(&self) -> LobbyMessage {
+        match &self.role {
+            Role::Host { secret } => LobbyMessage::Open {
+                lobby: self.lobby.clone(),
+                player: self.player.clone(),
+                secret: secret.clone(),
+            },
+            Role::Guesser { nickname } => LobbyMessage::Join {
+                lobby: self.lobby.clone(),
+                player: self.player.clone(),
+                nickname: nickname.clone(),
+            },
+        }
+    }
+
+    fn can_guess(&self) -> bool {
+        matches!(self.role, Role::Guesser { .. }) && self.finished.is_none()
+    }
+}
+
+pub struct GroupComponent {
+    link: ComponentLink<Self>,
+    session: Option<Session>,
+
+    server: ServerLink,
+
+    error: Option<String>,
+    lobby_input_ref: NodeRef,
+    secret_input_ref: NodeRef,
+    nickname_input_ref: NodeRef,
+    guess_input_ref: NodeRef,
+}
+
+pub enum Msg {
+    Open,
+    Join,
+    Connect,
+    Status(WebSocketStatus),
+    Received(Text),
+    Guess,
+}
+
+impl GroupComponent {
+    fn start(&mut self, host: bool) -> Result<(), String> {
+        let lobby = get_value(&self.lobby_input_ref).trim().to_string();
+        if lobby.is_empty() || lobby.contains('\n') {
+            return Err("Enter a lobby name to share with the other players".to_string());
+        }
+        let role = if host {
+            let secret = get_value(&self.secret_input_ref).trim().to_string();
+            if secret.is_empty() {
+                return Err("Enter a secret".to_string());
+            }
+            Role::Host { secret }
+        } else {
+            let nickname = get_value(&self.nickname_input_ref).trim().to_string();
+            if nickname.is_empty() {
+                return Err("Enter a nickname".to_string());
+            }
+            Role::Guesser { nickname }
+        };
+        self.session = Some(Session {
+            lobby,
+            player: random_player_id(),
+            role,
+            length: None,
+            guesses: Vec::new(),
+            leaderboard: Vec::new(),
+            finished: None,
+        });
+        self.connect();
+        Ok(())
+    }
+
+    fn connect(&mut self) {
+        if let Err(e) = self.server.connect() {
+            self.error = Some(e);
+        }
+    }
+
+    fn send(&mut self, message: &LobbyMessage) {
+        self.server.send(message.encode());
+    }
+
+    fn received(&mut self, message: LobbyMessage) {
+        let session = match &mut self.session {
+            Some(session) => session,
+            None => return,
+        };
+        match message {
+            LobbyMessage::Joined { length } => {
+                session.length = Some(length);
+                // The server sends our guesses again after this.
+                session.guesses.clear();
+                self.server.set_joined();
+                self.error = None;
+            }
+            LobbyMessage::Scored { guess, stats } => session.guesses.push((guess, stats)),
+            LobbyMessage::Leaderboard { entries } => session.leaderboard = entries,
+            LobbyMessage::Finished {
+                winner,
+                secret,
+                guesses,
+            } => {
+                session.finished = Some(Finished {
+                    winner,
+                    secret,
+                    guesses,
+                })
+            }
+            LobbyMessage::Error { message } => {
+                // Not being let in, e.g. because the nickname is taken, won't fix itself by reconnecting.
+                if !self.server.is_joined() {
+                    self.session = None;
+                    self.server.close();
+                }
+                self.error = Some(message);
+            }
+            LobbyMessage::Open { .. } | LobbyMessage::Join { .. } | LobbyMessage::Guess { .. } => {}
+        }
+    }
+
+    fn guess(&mut self) -> Result<(), String> {
+        let guess = get_value(&self.guess_input_ref).trim().to_string();
+        let session = match &self.session {
+            Some(session) if session.can_guess() => session,
+            _ => return Err("You can't guess in this lobby".to_string()),
+        };
+        if !self.server.is_joined() {
+            return Err("Not connected to the lobby. Try again in a moment".to_string());
+        }
+        if Some(guess.chars().count()) != session.length {
+            return Err(format!(
+                "Guesses must be {} characters long",
+                session.length.unwrap_or(0)
+            ));
+        }
+        self.send(&LobbyMessage::Guess { guess });
+        Ok(())
+    }
+
+    fn render_status(&self, session: &Session) -> Html {
+        let status = if let Some(finished) = &session.finished {
+            match &session.role {
+                Role::Guesser { nickname } if *nickname == finished.winner => {
+                    "You cracked the secret first. You win!".to_string()
+                }
+                _ => format!("{} cracked the secret first.", finished.winner),
+            }
+        } else if !self.server.is_joined() {
+            "Connecting to the lobby...".to_string()
+        } else {
+            match &session.role {
+                Role::Host { secret } => format!(
+                    "Your secret is {}. Share the lobby name {} with the guessers.",
+                    secret, session.lobby
+                ),
+                Role::Guesser { .. } => format!(
+                    "Guess the {} character secret before anyone else does.",
+                    session.length.unwrap_or(0)
+                ),
+            }
+        };
+        html! {<p class="status">{status}</p>}
+    }
+
+    fn render_leaderboard(&self, session: &Session) -> Html {
+        html! {
+            <table class="leaderboard">
+                <tr><th>{"Player"}</th><th>{"Guesses"}</th><th>{"Best result"}</th></tr>
+                {for session.leaderboard.iter().map(|entry| html! {
+                    <tr>
+                        <td>{&entry.nickname}</td>
+                        <td>{entry.guesses}</td>
+                        <td>{entry.best.map(|best| best.to_string()).unwrap_or_default()}</td>
+                    </tr>
+                })}
+            </table>
+        }
+    }
+
+    fn render_finished(&self, finished: &Finished) -> Html {
+        html! {
+            <div>
+                <p>{format!("The secret was {}", finished.secret)}</p>
+                {for finished.guesses.iter().map(|player| html! {
+                    <div>
+                        <h3>{&player.nickname}</h3>
+                        <ul>
+                            {for player.guesses.iter().map(|(guess, result)| html! {<li>{guess}{" - "}{result}</li>})}
+                        </ul>
+                    </div>
+                })}
+            </div>
+        }
+    }
+
+    fn render_session(&self, session: &Session) -> Html {
+        html! {
+            <div>
+                {self.render_status(session)}
+                <h2>{"Leaderboard"}</h2>
+                {self.render_leaderboard(session)}
+                {
+                    match &session.finished {
+                        Some(finished) => self.render_finished(finished),
+                        None => html! {},
+                    }
+                }
+                {
+                    if session.can_guess() {
+                        html! {
+                            <div>
+                                <h2>{"Your guesses"}</h2>
+                                <ul>
+                                    {for session.guesses.iter().map(|(guess, result)| html! {<li>{guess}{" - "}{result}</li>})}
+                                </ul>
+                                <label for="group_guess">{"Guess"}</label>
+                                <input type="text" id="group_guess" ref=self.guess_input_ref.clone()/>
+                                <input type="submit" value="Guess" onclick=self.link.callback(|_| Msg::Guess)/>
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+
+    fn render_start(&self) -> Html {
+        html! {
+            <div>
+                <label for="group_lobby">{"Lobby"}</label>
+                <input type="text" id="group_lobby" ref=self.lobby_input_ref.clone()/>
+                <p>
+                    <label for="group_secret">{"Secret"}</label>
+                    <input type="text" id="group_secret" ref=self.secret_input_ref.clone()/>
+                    <input type="submit" value="Open a lobby" onclick=self.link.callback(|_| Msg::Open)/>
+                </p>
+                <p>
+                    <label for="group_nickname">{"Nickname"}</label>
+                    <input type="text" id="group_nickname" ref=self.nickname_input_ref.clone()/>
+                    <input type="submit" value="Join a lobby" onclick=self.link.callback(|_| Msg::Join)/>
+                </p>
+            </div>
+        }
+    }
+}
+
+impl Component for GroupComponent {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            session: None,
+            server: ServerLink::new(
+                option_env!("PBF_LOBBY_URL").unwrap_or(DEFAULT_LOBBY_URL),
+                link.callback(Msg::Received),
+                link.callback(Msg::Status),
+                link.callback(|_| Msg::Connect),
+            ),
+            link,
+            error: None,
+            lobby_input_ref: NodeRef::default(),
+            secret_input_ref: NodeRef::default(),
+            nickname_input_ref: NodeRef::default(),
+            guess_input_ref: NodeRef::default(),
+        }
+    }
+    fn update(&mut self, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Open | Msg::Join => {
+                if let Err(e) = self.start(matches!(msg, Msg::Open)) {
+                    self.error = Some(e);
+                }
+            }
+            Msg::Connect => self.connect(),
+            Msg::Status(WebSocketStatus::Opened) => {
+                if let Some(session) = &self.session {
+                    let join = session.join_message();
+                    self.send(&join);
+                }
+            }
+            Msg::Status(_) => {
+                if matches!(&self.session, Some(session) if session.finished.is_none()) {
+                    self.error =
+                        Some("Lost the connection to the lobby. Reconnecting...".to_string());
+                    self.server.schedule_reconnect();
+                }
+            }
+            Msg::Received(Ok(text)) => match LobbyMessage::decode(&text) {
+                Ok(message) => self.received(message),
+                Err(e) => self.error = Some(e),
+            },
+            Msg::Received(Err(_)) => {}
+            Msg::Guess => match self.guess() {
+                Ok(()) => self.error = None,
+                Err(e) => self.error = Some(e),
+            },
+        }
+        true
+    }
+    fn change(&mut self, _: Self::Properties) -> bool {
+        false
+    }
+    fn view(&self) -> Html {
+        html! {
+            <div>
+                <h1>{"Group game"}</h1>
+                <p>{"One player opens a lobby with a secret, and everyone who joins races to crack it. The leaderboard shows how everyone is doing, but nobody sees anyone else's guesses until someone wins."}</p>
+                {
+                    match &self.session {
+                        Some(session) => self.render_session(session),
+                        None => self.render_start(),
+                    }
+                }
+                <span class="error">{self.error.as_ref().unwrap_or(&"".to_string())}</span>
+                <p><a href="/">{"Back to the main game"}</a></p>
+            </div>
+        }
+    }
+}
@@ -1,70 +1,169 @@
+use crate::game::GuessSpace;
+use crate::pbf::explain::explain;
+use crate::pbf::session::{
+    export_session, parse_alphabet, parse_guess, parse_session, DEFAULT_SYMBOLS,
+};
+use crate::pbf::solver::can_enumerate;
 use crate::pbf::solver::Guess;
 use crate::pbf::solver::GuessState;
+use crate::pbf::solver::KnowledgeGrid;
+use crate::render_knowledge_grid;
 use crate::PbfStats;
-use web_sys::HtmlInputElement;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
 use yew::{html, Component, ComponentLink, Html, NodeRef};
 
-type Digit = u8;
+// What the page starts with unless the url says otherwise, e.g. /hint.html?alphabet=0123456789abcdef&length=4.
+const DEFAULT_LENGTH: usize = 3;
 
 pub struct HintComponent {
     link: ComponentLink<Self>,
-    guess_state: GuessState<Digit>,
+    // The symbols secrets are made of, in the order they're tried.
+    alphabet: Vec<GuessSpace>,
+    guess_state: GuessState<GuessSpace>,
+    // Kept in step with guess_state.
+    knowledge: KnowledgeGrid<GuessSpace>,
     error: Option<String>,
-    hint: Option<Vec<Digit>>,
+    hint: Option<Vec<GuessSpace>>,
+    show_reasoning: bool,
+    alphabet_ref: NodeRef,
+    length_ref: NodeRef,
     guess_digits_ref: NodeRef,
     guess_result_ref: NodeRef,
+    session_ref: NodeRef,
 }
 
 pub enum Msg {
+    ChangeGame,
     AddGuess,
     Reset,
     ComputeHint,
+    Import,
+    Export,
+    ToggleReasoning,
 }
 
 fn get_value(node_ref: &NodeRef) -> String {
     node_ref.cast::<HtmlInputElement>().unwrap().value()
 }
 
+fn parse_length(length: &str) -> Result<usize, String> {
+    match length.trim().parse() {
+        Ok(length) if length > 0 => Ok(length),
+        _ => Err(format!("{} is not a valid length", length)),
+    }
+}
+
+fn new_guess_state(
+    alphabet: &[GuessSpace],
+    length: usize,
+) -> Result<GuessState<GuessSpace>, String> {
+    if !can_enumerate(alphabet.len(), length) {
+        return Err(format!(
+            "There are too many possible secrets with {} symbols and length {}",
+            alphabet.len(),
+            length
+        ));
+    }
+    Ok(GuessState::new(alphabet.to_vec(), length))
+}
+
+// The alphabet and length from the url's alphabet= and length= parameters, falling back to the defaults for any that are missing.
+fn linked_game() -> Result<(Vec<GuessSpace>, usize), String> {
+    let mut alphabet = DEFAULT_SYMBOLS.to_string();
+    let mut length = DEFAULT_LENGTH;
+    let search = web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .unwrap_or_default();
+    for parameter in search.trim_start_matches('?').split('&') {
+        let (key, value) = match parameter.split_once('=') {
+            Some(field) => field,
+            None => continue,
+        };
+        let value: String = js_sys::decode_uri_component(value)
+            .map(String::from)
+            .map_err(|_ignored| format!("{} is not a valid {}", value, key))?;
+        match key {
+            "alphabet" => alphabet = value,
+            "length" => length = parse_length(&value)?,
+            _ => {}
+        }
+    }
+    Ok((parse_alphabet(&alphabet)?, length))
+}
+
 impl HintComponent {
-    fn get_current_guess(&self) -> Result<Guess<Digit>, String> {
-        let digits: Result<Vec<Digit>, String> = get_value(&self.guess_digits_ref)
-            .chars()
-            .map(|c| {
-                c.to_digit(10)
-                    .ok_or(format!("{} is not a digit", c))
-                    .and_then(|i| {
-                        if i <= 9 {
-                            Ok(i as Digit)
-                        } else {
-                            Err(format!("{} must be a digit between 0 and 9 (inclusive)", i))
-                        }
-                    })
-            })
-            .collect();
-        let digits = digits?;
-        let results_string = get_value(&self.guess_result_ref);
-        let results_p = results_string.chars().filter(|c| *c == 'p').count();
-        let results_f = results_string.chars().filter(|c| *c == 'f').count();
-        Ok(Guess {
-            guess: digits,
-            result: PbfStats {
-                p: results_p as i32,
-                f: results_f as i32,
-            },
-        })
+    fn get_current_guess(&self) -> Result<Guess<GuessSpace>, String> {
+        parse_guess(
+            &self.alphabet,
+            &self.guess_state,
+            &get_value(&self.guess_digits_ref),
+            &get_value(&self.guess_result_ref),
+        )
+    }
+
+    fn set_guess_state(&mut self, guess_state: GuessState<GuessSpace>) {
+        self.knowledge = guess_state.knowledge_grid();
+        self.guess_state = guess_state;
+    }
+
+    // The same game with no guesses yet.
+    fn fresh_guess_state(&self) -> GuessState<GuessSpace> {
+        GuessState::new(self.alphabet.clone(), self.guess_state.guess_length())
+    }
+
+    fn change_game(&mut self) -> Result<(), String> {
+        let alphabet = parse_alphabet(&get_value(&self.alphabet_ref))?;
+        let length = parse_length(&get_value(&self.length_ref))?;
+        self.set_guess_state(new_guess_state(&alphabet, length)?);
+        self.alphabet = alphabet;
+        self.hint = None;
+        Ok(())
+    }
+
+    fn session_input(&self) -> HtmlTextAreaElement {
+        self.session_ref.cast::<HtmlTextAreaElement>().unwrap()
     }
 
     fn render_hint(&self) -> Html {
         if let Some(hint) = &self.hint {
-            html! {<p>{hint.iter().map(|digit|format!("{}",digit)).collect::<Vec<String>>().join("")}</p>}
+            html! {<p>{hint.iter().collect::<String>()}</p>}
         } else {
             html! {<p>{"No hint available."}</p>}
         }
     }
-}
 
-fn default_guess_state() -> GuessState<Digit> {
-    GuessState::new((0..10).collect(), 3)
+    fn render_reasoning(&self) -> Html {
+        if !self.show_reasoning {
+            return html! {
+                <input type="submit" value="Show reasoning" onclick={self.link.callback(|_|Msg::ToggleReasoning)}/>
+            };
+        }
+        let statements = explain(self.guess_state.guesses());
+        html! {
+            <div class="reasoning">
+                <input type="submit" value="Hide reasoning" onclick={self.link.callback(|_|Msg::ToggleReasoning)}/>
+                {
+                    if statements.is_empty() {
+                        html! {<p>{"Nothing can be worked out for certain from these guesses yet."}</p>}
+                    } else {
+                        html! {<ol>{for statements.iter().map(|statement| html! {<li>{statement}</li>})}</ol>}
+                    }
+                }
+            </div>
+        }
+    }
+
+    fn render_game(&self) -> Html {
+        html! {
+            <div>
+                <label for="hint_alphabet">{"Symbols"}</label>
+                <input type="text" id="hint_alphabet" ref={self.alphabet_ref.clone()} value={self.alphabet.iter().collect::<String>()}/>
+                <label for="hint_length">{"Length"}</label>
+                <input type="number" min="1" id="hint_length" ref={self.length_ref.clone()} value={self.guess_state.guess_length().to_string()}/>
+                <input type="submit" value="Start over with these" onclick={self.link.callback(|_|Msg::ChangeGame)}/>
+            </div>
+        }
+    }
 }
 
 impl Component for HintComponent {
@@ -72,21 +171,46 @@ impl Component for HintComponent {
     type Properties = ();
 
     fn create(_: Self::Properties, link: yew::html::Scope<Self>) -> Self {
+        let linked = linked_game()
+            .and_then(|(alphabet, length)| Ok((new_guess_state(&alphabet, length)?, alphabet)));
+        let (guess_state, alphabet, error) = match linked {
+            Ok((guess_state, alphabet)) => (guess_state, alphabet, None),
+            Err(e) => {
+                let alphabet: Vec<GuessSpace> = DEFAULT_SYMBOLS.chars().collect();
+                (
+                    GuessState::new(alphabet.clone(), DEFAULT_LENGTH),
+                    alphabet,
+                    Some(e),
+                )
+            }
+        };
         Self {
             link,
-            guess_state: default_guess_state(),
-            error: None,
+            alphabet,
+            knowledge: guess_state.knowledge_grid(),
+            guess_state,
+            error,
             hint: None,
+            show_reasoning: false,
+            alphabet_ref: NodeRef::default(),
+            length_ref: NodeRef::default(),
             guess_digits_ref: NodeRef::default(),
             guess_result_ref: NodeRef::default(),
+            session_ref: NodeRef::default(),
         }
     }
     fn update(&mut self, msg: Self::Message) -> bool {
         match msg {
+            Msg::ChangeGame => {
+                self.error = self.change_game().err();
+                true
+            }
             Msg::AddGuess => {
                 match self.get_current_guess() {
                     Ok(guess) => {
                         self.guess_state.add_guess(guess);
+                        self.knowledge = self.guess_state.knowledge_grid();
+                        self.error = None;
                     }
                     Err(e) => {
                         self.error = Some(e);
@@ -95,13 +219,40 @@ impl Component for HintComponent {
                 true
             }
             Msg::Reset => {
-                self.guess_state = default_guess_state();
+                self.set_guess_state(self.fresh_guess_state());
                 true
             }
             Msg::ComputeHint => {
                 self.hint = self.guess_state.next_guess();
                 true
             }
+            // Replaces the guesses so far, but only if the whole session can be read.
+            Msg::Import => {
+                let mut guess_state = self.fresh_guess_state();
+                match parse_session(&self.alphabet, &guess_state, &self.session_input().value()) {
+                    Ok(guesses) => {
+                        for guess in guesses {
+                            guess_state.add_guess(guess);
+                        }
+                        self.set_guess_state(guess_state);
+                        self.hint = None;
+                        self.error = None;
+                    }
+                    Err(errors) => {
+                        self.error = Some(errors.join("\n"));
+                    }
+                }
+                true
+            }
+            Msg::ToggleReasoning => {
+                self.show_reasoning = !self.show_reasoning;
+                true
+            }
+            Msg::Export => {
+                self.session_input()
+                    .set_value(&export_session(&self.guess_state));
+                false
+            }
         }
     }
     fn change(&mut self, _: <Self as yew::Component>::Properties) -> bool {
@@ -112,6 +263,7 @@ impl Component for HintComponent {
             <div>
                 <h1>{"Hints"}</h1>
                 <p>{"Enter your current guesses and their results to get a hint on what to guess next."}</p>
+                {self.render_game()}
                 <h2>{"Guesses"}</h2>
                 {render_guesses(&self.guess_state)}
                 <label for="guess_digits">{"Guess"}</label><input type="text" ref={self.guess_digits_ref.clone()} id="guess_digits"/>
@@ -120,13 +272,21 @@ impl Component for HintComponent {
                 <input type="submit" value="Clear guesses" onclick={self.link.callback(|_|Msg::Reset)}/>
                 <input type="submit" value="Compute hint" onclick={self.link.callback(|_|Msg::ComputeHint)}/>
                 <span class="hint">{self.render_hint()}</span>
-                <span class="error">{self.error.as_ref().unwrap_or(&"".to_string())}</span>
+                <h2>{"What's known"}</h2>
+                {render_knowledge_grid(&self.knowledge)}
+                {self.render_reasoning()}
+                <h2>{"Import or export"}</h2>
+                <p>{"Paste one guess and its outcome per line, like \"123 fp\", to replace the guesses above, or export them to copy elsewhere."}</p>
+                <textarea rows="6" cols="20" ref={self.session_ref.clone()} id="hint_session"/>
+                <input type="submit" value="Import guesses" onclick={self.link.callback(|_|Msg::Import)}/>
+                <input type="submit" value="Export guesses" onclick={self.link.callback(|_|Msg::Export)}/>
+                <pre class="error">{self.error.as_ref().unwrap_or(&"".to_string())}</pre>
             </div>
         }
     }
 }
 
-fn render_guesses(guess_state: &GuessState<Digit>) -> Html {
+fn render_guesses(guess_state: &GuessState<GuessSpace>) -> Html {
     html! {
         <ul>
           {for guess_state.guesses().iter().map(|guess|render_guess(&guess.guess, &guess.result))}
@@ -134,6 +294,6 @@ fn render_guesses(guess_state: &GuessState<Digit>) -> Html {
     }
 }
 
-fn render_guess(guess: &Vec<Digit>, results: &PbfStats) -> Html {
-    html! {<li>{guess.iter().map(|c|format!("{}",c)).collect::<Vec<String>>().join("")} {" - "} {results}</li>}
+fn render_guess(guess: &[GuessSpace], results: &PbfStats) -> Html {
+    html! {<li>{guess.iter().collect::<String>()} {" - "} {results}</li>}
 }
@@ -1,51 +1,72 @@
 #![recursion_limit = "512"]
 
 mod arrays;
+mod clipboard;
 mod crypto;
+mod custody;
+mod daily;
+mod evil;
+mod game;
+mod group;
 mod hint_component;
+pub mod lobby;
+mod mastermind;
 mod once;
-mod pbf;
-mod secret;
+pub mod pbf;
+mod puzzle;
+pub mod relay;
+mod replay;
+mod rules;
+pub mod secret;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+mod server_link;
+mod verify;
+mod versus;
+mod words;
 
+use crate::custody::{custody_url, linked_game, Session, GAME_QUERY_PREFIX};
+use crate::evil::EvilComponent;
+use crate::game::{GameStatus, GuessSpace, GuessState, Hint, ScoredGuess, MAX_HINT_LEVEL};
+use crate::group::GroupComponent;
 use crate::hint_component::HintComponent;
+use crate::mastermind::{render_code, render_pegs, ColorPicker};
 use crate::once::OnceCellContent;
-use crate::pbf::PbfStats;
-use crate::secret::Secret;
+use crate::pbf::analysis::GuessAnalysis;
+use crate::pbf::solver::{Knowledge, KnowledgeGrid};
+use crate::pbf::{Feedback, PbfStats, PositionResult};
+use crate::puzzle::PuzzleComponent;
+use crate::replay::{Replay, ReplayComponent};
+use crate::rules::{Consistency, Rules, SecretPayload, Variant};
+use crate::verify::VerifyComponent;
+use crate::versus::VersusComponent;
 use arrays::make_typed_array;
+use clipboard::copy_to_clipboard;
 use crypto::{decrypt, encrypt_secret_value, subtle, AES_CBC_PARAMS, IV_BYTES, KEY, KEY_BYTES};
+use daily::{daily_game, puzzle_number, today, DailyRecord};
 
 use futures::FutureExt;
 use lazy_static::lazy_static;
 use std::future::Future;
 use std::pin::Pin;
 use wasm_bindgen::prelude::*;
-use web_sys;
 use web_sys::AesCbcParams;
 use web_sys::HtmlInputElement;
+use web_sys::HtmlSelectElement;
 use yew::prelude::*;
 use yew_router::{route::Route, service::RouteService, Switch};
 use yewtil::future::LinkFuture;
 
-type GuessSpace = char;
-
-struct GuessState {
-    // Map from character to position.
-    secret: Secret<GuessSpace>,
-    secret_length: usize,
-    guesses: Vec<String>,
-}
-
 lazy_static! {
-    static ref EXAMPLE_GUESS_STATE: GuessState = GuessState {
-        secret: Secret::new(vec!['1', '2', '3']),
-        secret_length: 3,
-        guesses: vec![
-            "789".to_string(),
-            "345".to_string(),
-            "234".to_string(),
-            "134".to_string(),
-            "123".to_string()
-        ]
+    static ref EXAMPLE_GUESS_STATE: GuessState = {
+        let mut guess_state = GuessState::new(SecretPayload {
+            secrets: vec!["123".to_string()],
+            rules: Rules::default(),
+        });
+        for guess in &["789", "345", "234", "134", "123"] {
+            guess_state.guess(guess.to_string());
+        }
+        guess_state
     };
 }
 
@@ -53,6 +74,20 @@ lazy_static! {
 enum AppRoute {
     #[to = "/hint.html"]
     Hint,
+    #[to = "/daily.html"]
+    Daily,
+    #[to = "/versus.html"]
+    Versus,
+    #[to = "/group.html"]
+    Group,
+    #[to = "/replay.html"]
+    Replay,
+    #[to = "/puzzle.html"]
+    Puzzle,
+    #[to = "/verify.html"]
+    Verify,
+    #[to = "/evil.html"]
+    Evil,
     #[to = "/"]
     Index,
 }
@@ -63,7 +98,7 @@ enum Mode {
     LoadingSecret,
     EncryptingSecret,
 
-    // Value is the base64-encoded, encrypted, secret.
+    // Value is the base64-encoded, encrypted, secret, or game=<id> for games kept by a custody server.
     CreatedSecret(String),
     Guess(GuessState),
 }
@@ -72,25 +107,59 @@ struct Model {
     link: ComponentLink<Self>,
     mode: Mode,
     invalid_url: bool,
+    create_secret_error: Option<String>,
+    guess_error: Option<String>,
+    // Set when the last guess couldn't have been the secret and the rules say to point that out.
+    guess_warning: Option<String>,
+    // Whether copying the share text worked, once it has been tried.
+    share_copied: Option<bool>,
     secret_input_ref: NodeRef,
+    max_guesses_input_ref: NodeRef,
+    variant_input_ref: NodeRef,
+    consistency_input_ref: NodeRef,
+    positional_feedback_input_ref: NodeRef,
     next_guess_input_ref: NodeRef,
     route: Route<()>,
+    // Set when playing the daily puzzle, to the day being played.
+    daily_day: Option<i64>,
+    daily_record: Option<DailyRecord>,
+    // Set when the secrets are kept by a custody server, to this guesser's session with it.
+    custody_session: Option<String>,
+    // The last hint asked for, until the next guess.
+    hint: Option<Hint>,
+    // Set once the guesser asks to see the knowledge grid, and kept up to date after that.
+    knowledge: Option<Result<Vec<KnowledgeGrid<GuessSpace>>, String>>,
 }
 
 enum Msg {
     CreateSecret,
     Guess,
+    // A guess made with the color picker rather than typed in.
+    PickedGuess(String),
+    GiveUp,
+    GetHint,
+    ShowKnowledge,
+    CopyShareText,
+    ShareTextCopied(bool),
     StartLoadingSecret,
     SecretLoadFailure,
     SecretEncryptFailure,
     SecretLoaded(String),
     SecretEncrypted(String),
+    // Messages for games kept by a custody server.
+    GameCreateFailure(String),
+    SessionStarted(Session),
+    ScoredGuess(String, ScoredGuess),
+    GuessFailure(String),
+    ScoredGiveUp(ScoredGuess),
 }
 
+type SecretValueFuture = Pin<Box<dyn Future<Output = Result<String, ()>>>>;
+
 // Reads the query portion of the url, decodes as base-64, decrypts, and returns the decrypted string.
 // If anything fails (e.g. the user made an invalid url) returns an error.
 // If there was no query, returns None.
-fn get_secret_value() -> Option<Pin<Box<dyn Future<Output = Result<String, ()>>>>> {
+fn get_secret_value() -> Option<SecretValueFuture> {
     let search_text_result = web_sys::window()
         .expect("Need window feature enabled")
         .location()
@@ -103,27 +172,365 @@ fn get_secret_value() -> Option<Pin<Box<dyn Future<Output = Result<String, ()>>>
             .map(|s| base64::decode(&s[1..])) // 1.. to skip the ? at the beginning.
             .map(|encrypted_data| {
                 if let Ok(mut encrypted_data) = encrypted_data {
-                    let ret: Pin<Box<dyn Future<Output = Result<String, ()>>>> =
-                        Box::pin(Box::new(decrypt(&mut encrypted_data).map(
-                            |decrypted_data: Result<Vec<u8>, ()>| {
-                                decrypted_data.and_then(|decrypted_data: Vec<u8>| {
-                                    std::str::from_utf8(&decrypted_data)
-                                        .map(|s| s.to_string())
-                                        .map_err(|_ignored| ())
-                                })
-                            },
-                        )));
+                    let ret: SecretValueFuture = Box::pin(Box::new(
+                        decrypt(&mut encrypted_data).map(|decrypted_data: Result<Vec<u8>, ()>| {
+                            decrypted_data.and_then(|decrypted_data: Vec<u8>| {
+                                std::str::from_utf8(&decrypted_data)
+                                    .map(|s| s.to_string())
+                                    .map_err(|_ignored| ())
+                            })
+                        }),
+                    ));
                     ret
                 } else {
-                    let ret: Pin<Box<dyn Future<Output = Result<String, ()>>>> = Box::pin(
-                        Box::new(futures::future::ready::<Result<String, ()>>(Err(()))),
-                    );
+                    let ret: SecretValueFuture = Box::pin(Box::new(futures::future::ready::<
+                        Result<String, ()>,
+                    >(Err(()))));
                     ret
                 }
             })
     }
 }
 
+impl Model {
+    // Reads the secrets for a new game from the create-secret form. Several secrets can be given, separated by spaces.
+    fn read_secrets(&self, rules: &Rules) -> Result<Vec<String>, String> {
+        let secrets: Vec<String> = self
+            .secret_input_ref
+            .cast::<HtmlInputElement>()
+            .unwrap()
+            .value()
+            .split_whitespace()
+            .map(|secret| secret.to_string())
+            .collect();
+        match secrets.first() {
+            None => Err("Enter a secret".to_string()),
+            Some(first) => {
+                for secret in &secrets {
+                    rules.variant.validate_secret(secret)?;
+                }
+                let length = first.chars().count();
+                if secrets
+                    .iter()
+                    .all(|secret| secret.chars().count() == length)
+                {
+                    Ok(secrets)
+                } else {
+                    Err("All secrets must be the same length".to_string())
+                }
+            }
+        }
+    }
+
+    // Reads the rules for a new game from the create-secret form.
+    fn read_rules(&self) -> Result<Rules, String> {
+        let max_guesses = self
+            .max_guesses_input_ref
+            .cast::<HtmlInputElement>()
+            .unwrap()
+            .value();
+        let max_guesses = if max_guesses.trim().is_empty() {
+            None
+        } else {
+            match max_guesses.trim().parse::<usize>() {
+                Ok(max_guesses) if max_guesses > 0 => Some(max_guesses),
+                _ => return Err(format!("{} is not a valid number of guesses", max_guesses)),
+            }
+        };
+        let variant = match self
+            .variant_input_ref
+            .cast::<HtmlSelectElement>()
+            .unwrap()
+            .value()
+            .as_str()
+        {
+            "mastermind" => Variant::Mastermind,
+            "bulls_and_cows" => Variant::BullsAndCows,
+            "words" => Variant::Words,
+            _ => Variant::PicoBagelFermi,
+        };
+        let consistency = match self
+            .consistency_input_ref
+            .cast::<HtmlSelectElement>()
+            .unwrap()
+            .value()
+            .as_str()
+        {
+            "soft" => Consistency::Soft,
+            "hard" => Consistency::Hard,
+            _ => Consistency::Unchecked,
+        };
+        let feedback = if self
+            .positional_feedback_input_ref
+            .cast::<HtmlInputElement>()
+            .unwrap()
+            .checked()
+        {
+            Feedback::Positions
+        } else {
+            Feedback::Counts
+        };
+        Ok(Rules {
+            max_guesses,
+            variant,
+            feedback,
+            consistency,
+        })
+    }
+
+    fn make_guess(&mut self, guess: String) {
+        if let Mode::Guess(ref mut guess_state) = self.mode {
+            if let Err(e) = guess_state.validate_guess(&guess) {
+                self.guess_error = Some(e);
+                return;
+            }
+            self.guess_error = None;
+            self.guess_warning = guess_state.guess_warning(&guess);
+            self.hint = None;
+            if let Some(session) = &self.custody_session {
+                let scored_future = custody::guess(session.clone(), guess.clone());
+                self.link.send_future(async {
+                    match scored_future.await {
+                        Ok(scored) => Msg::ScoredGuess(guess, scored),
+                        Err(e) => Msg::GuessFailure(e),
+                    }
+                });
+                return;
+            }
+            guess_state.guess(guess);
+            if guess_state.is_over() {
+                guess_state.analysis = guess_state.analyze();
+            }
+        } else {
+            panic!("Guessing while not in guess state");
+        }
+        self.refresh_knowledge();
+        self.update_daily_record();
+    }
+
+    fn refresh_knowledge(&mut self) {
+        if let (Some(knowledge), Mode::Guess(guess_state)) = (&mut self.knowledge, &self.mode) {
+            *knowledge = guess_state.knowledge();
+        }
+    }
+
+    // Saves the guesses and hints on the daily puzzle, so reloading the page picks up the same attempt.
+    fn save_daily_progress(&mut self) {
+        if let (Some(day), Some(record), Mode::Guess(guess_state)) =
+            (self.daily_day, &mut self.daily_record, &self.mode)
+        {
+            record.save_progress(day, &guess_state.guesses, guess_state.hints_used);
+        }
+    }
+
+    // Saves progress on the daily puzzle after each move, so it can't be replayed by reloading the page.
+    fn update_daily_record(&mut self) {
+        self.save_daily_progress();
+        if let (Some(day), Some(record), Mode::Guess(guess_state)) =
+            (self.daily_day, &mut self.daily_record, &self.mode)
+        {
+            if guess_state.is_over() {
+                record.record_result(day, guess_state.status() == GameStatus::Won);
+            }
+        }
+    }
+
+    fn share_title(&self) -> String {
+        match self.daily_day {
+            Some(day) => format!("Pico Bagel Fermi daily #{}", puzzle_number(day)),
+            None => "Pico Bagel Fermi".to_string(),
+        }
+    }
+
+    fn render_daily(&self) -> Html {
+        let streak_html = match (self.daily_day, &self.daily_record) {
+            (Some(day), Some(record)) => {
+                html! {<p class="streak">{format!("Current streak: {}", record.streak(day))}</p>}
+            }
+            _ => html! {<p>{"Your streak can't be saved in this browser."}</p>},
+        };
+        let guess_html = match &self.mode {
+            Mode::Guess(guess_state) => self.render_guess_view(guess_state),
+            _ => html! {},
+        };
+        html! {
+            <div>
+                <h1>{format!("Pico Bagel Fermi daily #{}", self.daily_day.map(puzzle_number).unwrap_or_default())}</h1>
+                <p>{"Everyone gets the same three-digit secret today, with no repeated digits. You get one attempt."}</p>
+                {streak_html}
+                {guess_html}
+                <p><a href="/">{"Back to the main game"}</a></p>
+            </div>
+        }
+    }
+
+    fn render_share(&self, guess_state: &GuessState) -> Html {
+        let copied_html = match self.share_copied {
+            Some(true) => html! {<span>{"Copied!"}</span>},
+            Some(false) => {
+                html! {<span class="error">{"Couldn't copy, select the text above instead."}</span>}
+            }
+            None => html! {},
+        };
+        html! {
+            <div class="share">
+                <h3>{"Share your result"}</h3>
+                <pre>{guess_state.share_text(&self.share_title())}</pre>
+                <input type="submit" value="Copy to clipboard" onclick=self.link.callback(|_|Msg::CopyShareText)/>
+                {copied_html}
+            </div>
+        }
+    }
+
+    fn render_new_game(&self) -> Html {
+        html! {
+            <div>
+                <h2>{"New game"}</h2>
+                <p>{"To create a new game, enter a secret to guess below, and click \"Create new game.\""}</p>
+                <label for={"secret_number_input"}>{"Secret number (separate several secrets with spaces to have them all guessed at once)"}</label>
+                <input type="text" ref={self.secret_input_ref.clone()} id={"secret_number_input"}/>
+                <label for={"max_guesses_input"}>{"Maximum guesses (leave blank for no limit)"}</label>
+                <input type="number" min="1" ref={self.max_guesses_input_ref.clone()} id={"max_guesses_input"}/>
+                <label for={"variant_input"}>{"Game"}</label>
+                <select ref={self.variant_input_ref.clone()} id={"variant_input"}>
+                    <option value="pbf" selected=true>{"Pico Bagel Fermi"}</option>
+                    <option value="mastermind">{"Mastermind (four of R, O, Y, G, B, P; repeats allowed)"}</option>
+                    <option value="bulls_and_cows">{"Bulls and Cows (four different digits)"}</option>
+                    <option value="words">{"Words (a 4 or 5 letter lowercase word)"}</option>
+                </select>
+                <input type="checkbox" ref={self.positional_feedback_input_ref.clone()} id={"positional_feedback_input"}/>
+                <label for={"positional_feedback_input"}>{"Show which positions matched"}</label>
+                <label for={"consistency_input"}>{"Guesses that can't be the secret"}</label>
+                <select ref={self.consistency_input_ref.clone()} id={"consistency_input"}>
+                    <option value="unchecked" selected=true>{"Allow them"}</option>
+                    <option value="soft">{"Warn about them"}</option>
+                    <option value="hard">{"Don't allow them (hard mode)"}</option>
+                </select>
+                <input type="submit" value="Create new game" onclick=self.link.callback(|_|Msg::CreateSecret)/>
+                <span class="error">{self.create_secret_error.as_ref().unwrap_or(&"".to_string())}</span>
+            </div>
+        }
+    }
+
+    fn render_hint(&self) -> Html {
+        let hint_html = match &self.hint {
+            Some(hint) => {
+                let candidates: Vec<String> = hint
+                    .candidates
+                    .iter()
+                    .map(|count| count.to_string())
+                    .collect();
+                let clue_html = match (&hint.clue, hint.level) {
+                    (Some(clue), _) => html! {<p>{clue}</p>},
+                    (None, 1) => html! {},
+                    (None, _) => html! {<p>{"The solver isn't sure about any one symbol yet."}</p>},
+                };
+                let suggestion_html = match &hint.suggestion {
+                    Some(suggestion) => html! {<p>{format!("Try {}", suggestion)}</p>},
+                    None => html! {},
+                };
+                html! {
+                    <>
+                    <p>{format!("Possible secrets left: {}", candidates.join(", "))}</p>
+                    {clue_html}
+                    {suggestion_html}
+                    </>
+                }
+            }
+            None => html! {},
+        };
+        let level = self.hint.as_ref().map(|hint| hint.level).unwrap_or(0);
+        let button_html = match level {
+            0 => {
+                html! {<input type="submit" value="Get hint" onclick=self.link.callback(|_|Msg::GetHint)/>}
+            }
+            level if level < MAX_HINT_LEVEL => {
+                html! {<input type="submit" value="Get a bigger hint" onclick=self.link.callback(|_|Msg::GetHint)/>}
+            }
+            _ => html! {},
+        };
+        html! {
+            <div class="hint">
+                {button_html}
+                {hint_html}
+            </div>
+        }
+    }
+
+    fn render_knowledge(&self) -> Html {
+        match &self.knowledge {
+            None => html! {
+                <input type="submit" value="Show what's known about each symbol" onclick=self.link.callback(|_|Msg::ShowKnowledge)/>
+            },
+            Some(Ok(grids)) => html! {
+                <div class="knowledge">
+                    {for grids.iter().enumerate().map(|(index, grid)| html! {
+                        <>
+                        {if grids.len() > 1 { html! {<h3>{format!("Secret {}", index + 1)}</h3>} } else { html! {} }}
+                        {render_knowledge_grid(grid)}
+                        </>
+                    })}
+                </div>
+            },
+            Some(Err(e)) => html! {<p>{e}</p>},
+        }
+    }
+
+    fn render_guess_view(&self, guess_state: &GuessState) -> Html {
+        let next_guess_html = if guess_state.is_over() {
+            html! {
+                <>
+                {render_summary(guess_state)}
+                {self.render_share(guess_state)}
+                <p><a href={Replay::from_game(guess_state).link()}>{"Replay this game"}</a></p>
+                </>
+            }
+        } else {
+            let guesses_left_html = match guess_state.guesses_left() {
+                Some(guesses_left) => html! {<p>{format!("{} guesses left", guesses_left)}</p>},
+                None => html! {},
+            };
+            let consistency_html = match guess_state.rules.consistency {
+                Consistency::Hard => {
+                    html! {<p>{"Hard mode: every guess has to be one that could still be the secret."}</p>}
+                }
+                Consistency::Unchecked | Consistency::Soft => html! {},
+            };
+            let guess_input_html = match guess_state.rules.variant {
+                Variant::Mastermind => html! {
+                    <ColorPicker on_guess=self.link.callback(Msg::PickedGuess)/>
+                },
+                Variant::PicoBagelFermi | Variant::BullsAndCows | Variant::Words => html! {
+                    <>
+                    <label for="next_guess">{"Next guess"}</label>
+                    <input type="text" id="next_guess" ref={self.next_guess_input_ref.clone()}/>
+                    <input type="submit" onclick=self.link.callback(|_|Msg::Guess)/>
+                    </>
+                },
+            };
+            html! {
+                <>
+                {guesses_left_html}
+                {consistency_html}
+                {guess_input_html}
+                {self.render_hint()}
+                {self.render_knowledge()}
+                <input type="submit" value="Give up" onclick=self.link.callback(|_|Msg::GiveUp)/>
+                <span class="error">{self.guess_error.as_ref().unwrap_or(&"".to_string())}</span>
+                <span class="warning">{self.guess_warning.as_ref().unwrap_or(&"".to_string())}</span>
+                </>
+            }
+        };
+        html! {
+            <div class="guesses">
+            <h2>{"Try to guess what the hidden secret is."}</h2>
+                {render_guesses(guess_state)}
+                {next_guess_html}
+            </div>
+        }
+    }
+}
+
 impl Component for Model {
     type Message = Msg;
     type Properties = ();
@@ -132,20 +539,84 @@ impl Component for Model {
         let route_service: RouteService<()> = RouteService::new();
         let route = route_service.get_route();
         let secret_input_ref = NodeRef::default();
+        let max_guesses_input_ref = NodeRef::default();
+        let variant_input_ref = NodeRef::default();
+        let consistency_input_ref = NodeRef::default();
+        let positional_feedback_input_ref = NodeRef::default();
         let next_guess_input_ref = NodeRef::default();
-        link.send_message(Msg::StartLoadingSecret);
+        let daily_day;
+        let daily_record;
+        let mode;
+        if Some(AppRoute::Daily) == AppRoute::switch(route.clone()) {
+            let day = today();
+            daily_record = DailyRecord::new();
+            let mut guess_state = daily_game(day);
+            let saved_guesses = daily_record
+                .as_ref()
+                .map(|record| record.saved_guesses(day))
+                .unwrap_or_default();
+            guess_state.hints_used = daily_record
+                .as_ref()
+                .map(|record| record.saved_hints(day))
+                .unwrap_or(0);
+            for guess in saved_guesses {
+                if !guess_state.is_over() {
+                    guess_state.guess(guess);
+                }
+            }
+            if guess_state.is_over() {
+                guess_state.analysis = guess_state.analyze();
+            }
+            daily_day = Some(day);
+            mode = Mode::Guess(guess_state);
+        } else {
+            daily_day = None;
+            daily_record = None;
+            mode = Mode::Uninitialized;
+            // Replay and puzzle links carry a finished game or a puzzle rather than a secret to guess.
+            if !matches!(
+                AppRoute::switch(route.clone()),
+                Some(AppRoute::Replay) | Some(AppRoute::Puzzle)
+            ) {
+                link.send_message(Msg::StartLoadingSecret);
+            }
+        }
         Self {
             link,
             invalid_url: false,
-            mode: Mode::Uninitialized,
+            create_secret_error: None,
+            guess_error: None,
+            guess_warning: None,
+            share_copied: None,
+            mode,
             secret_input_ref,
+            max_guesses_input_ref,
+            variant_input_ref,
+            consistency_input_ref,
+            positional_feedback_input_ref,
             next_guess_input_ref,
             route,
+            daily_day,
+            daily_record,
+            custody_session: None,
+            hint: None,
+            knowledge: None,
         }
     }
     fn update(&mut self, msg: <Self as yew::Component>::Message) -> bool {
         match msg {
             Msg::StartLoadingSecret => {
+                if let Some(game) = linked_game() {
+                    let session_future = custody::start_session(game);
+                    self.link.send_future(async {
+                        match session_future.await {
+                            Ok(session) => Msg::SessionStarted(session),
+                            Err(_) => Msg::SecretLoadFailure,
+                        }
+                    });
+                    self.mode = Mode::LoadingSecret;
+                    return true;
+                }
                 let secret_value_result = get_secret_value();
                 if let Some(secret_value_future) = secret_value_result {
                     self.link.send_future(async {
@@ -161,12 +632,31 @@ impl Component for Model {
                 true
             }
             Msg::CreateSecret => {
-                let encrypted_future = encrypt_secret_value(
-                    self.secret_input_ref
-                        .cast::<HtmlInputElement>()
-                        .unwrap()
-                        .value(),
-                );
+                let payload = match self.read_rules().and_then(|rules| {
+                    self.read_secrets(&rules)
+                        .map(|secrets| SecretPayload { secrets, rules })
+                }) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        self.create_secret_error = Some(e);
+                        return true;
+                    }
+                };
+                self.create_secret_error = None;
+                if custody_url().is_some() {
+                    let game_future = custody::create_game(payload.encode());
+                    self.link.send_future(async {
+                        match game_future.await {
+                            Ok(game) => {
+                                Msg::SecretEncrypted(format!("{}{}", GAME_QUERY_PREFIX, game))
+                            }
+                            Err(e) => Msg::GameCreateFailure(e),
+                        }
+                    });
+                    self.mode = Mode::EncryptingSecret;
+                    return true;
+                }
+                let encrypted_future = encrypt_secret_value(payload.encode());
                 self.link.send_future(async {
                     match encrypted_future.await {
                         Ok(s) => Msg::SecretEncrypted(s),
@@ -182,11 +672,10 @@ impl Component for Model {
             }
             Msg::SecretEncryptFailure => todo!(),
             Msg::SecretLoaded(secret) => {
-                self.mode = Mode::Guess(GuessState {
-                    secret_length: secret.len(),
-                    secret: Secret::new(secret.chars().collect()),
-                    guesses: Vec::new(),
-                });
+                match SecretPayload::decode(&secret) {
+                    Ok(payload) => self.mode = Mode::Guess(GuessState::new(payload)),
+                    Err(_) => self.invalid_url = true,
+                }
                 true
             }
             Msg::SecretEncrypted(secret) => {
@@ -194,17 +683,125 @@ impl Component for Model {
                 true
             }
             Msg::Guess => {
+                let guess = self
+                    .next_guess_input_ref
+                    .cast::<HtmlInputElement>()
+                    .unwrap()
+                    .value();
+                self.make_guess(guess);
+                true
+            }
+            Msg::PickedGuess(guess) => {
+                self.make_guess(guess);
+                true
+            }
+            Msg::CopyShareText => {
+                if let Mode::Guess(guess_state) = &self.mode {
+                    let copied_future =
+                        copy_to_clipboard(&guess_state.share_text(&self.share_title()));
+                    self.link
+                        .send_future(async { Msg::ShareTextCopied(copied_future.await.is_ok()) });
+                }
+                false
+            }
+            Msg::ShareTextCopied(copied) => {
+                self.share_copied = Some(copied);
+                true
+            }
+            Msg::GameCreateFailure(e) => {
+                self.create_secret_error = Some(e);
+                self.mode = Mode::CreateSecret;
+                true
+            }
+            Msg::SessionStarted(session) => {
+                let mut guess_state = GuessState::held_by_server(
+                    session.secret_count,
+                    session.secret_length,
+                    session.rules,
+                );
+                // Coming back to a game picks it up where it was left.
+                for (guess, scored) in session.played {
+                    guess_state.guess_scored(guess, scored);
+                }
+                if session.status == GameStatus::GaveUp {
+                    guess_state.give_up();
+                }
+                guess_state.reveal(&session.secrets);
+                if guess_state.is_over() {
+                    guess_state.analysis = guess_state.analyze();
+                }
+                self.mode = Mode::Guess(guess_state);
+                self.custody_session = Some(session.id);
+                true
+            }
+            Msg::ScoredGuess(guess, scored) => {
+                if let Mode::Guess(ref mut guess_state) = self.mode {
+                    guess_state.guess_scored(guess, scored);
+                    if guess_state.is_over() {
+                        guess_state.analysis = guess_state.analyze();
+                    }
+                }
+                self.refresh_knowledge();
+                true
+            }
+            Msg::GuessFailure(e) => {
+                self.guess_error = Some(e);
+                true
+            }
+            Msg::ScoredGiveUp(scored) => {
+                if let Mode::Guess(ref mut guess_state) = self.mode {
+                    guess_state.give_up();
+                    guess_state.reveal(&scored.secrets);
+                    guess_state.analysis = guess_state.analyze();
+                }
+                true
+            }
+            Msg::GetHint => {
+                if let Mode::Guess(ref mut guess_state) = self.mode {
+                    let level = self.hint.as_ref().map(|hint| hint.level + 1).unwrap_or(1);
+                    match guess_state.take_hint(level.min(MAX_HINT_LEVEL)) {
+                        Ok(hint) => self.hint = Some(hint),
+                        Err(e) => self.guess_error = Some(e),
+                    }
+                }
+                self.save_daily_progress();
+                true
+            }
+            // Seeing the grid counts as one hint, however many guesses it's kept up to date for.
+            Msg::ShowKnowledge => {
+                if let Mode::Guess(ref mut guess_state) = self.mode {
+                    if self.knowledge.is_none() {
+                        match guess_state.knowledge() {
+                            Ok(grids) => {
+                                guess_state.hints_used += 1;
+                                self.knowledge = Some(Ok(grids));
+                            }
+                            Err(e) => self.guess_error = Some(e),
+                        }
+                    }
+                }
+                self.save_daily_progress();
+                true
+            }
+            Msg::GiveUp => {
+                if let Some(session) = &self.custody_session {
+                    let scored_future = custody::give_up(session.clone());
+                    self.link.send_future(async {
+                        match scored_future.await {
+                            Ok(scored) => Msg::ScoredGiveUp(scored),
+                            Err(e) => Msg::GuessFailure(e),
+                        }
+                    });
+                    return false;
+                }
                 if let Mode::Guess(ref mut guess_state) = self.mode {
-                    guess_state.guesses.push(
-                        self.next_guess_input_ref
-                            .cast::<HtmlInputElement>()
-                            .unwrap()
-                            .value(),
-                    );
-                    true
+                    guess_state.give_up();
+                    guess_state.analysis = guess_state.analyze();
                 } else {
-                    panic!("Guessing while not in guess state");
+                    panic!("Giving up while not in guess state");
                 }
+                self.update_daily_record();
+                true
             }
         }
     }
@@ -212,12 +809,46 @@ impl Component for Model {
         false // no properties to change.
     }
     fn view(&self) -> yew::virtual_dom::VNode {
-        if Some(AppRoute::Hint) == AppRoute::switch(self.route.clone()) {
-            return html! {
-                <HintComponent/>
-            };
+        match AppRoute::switch(self.route.clone()) {
+            Some(AppRoute::Hint) => {
+                return html! {
+                    <HintComponent/>
+                };
+            }
+            Some(AppRoute::Daily) => return self.render_daily(),
+            Some(AppRoute::Versus) => {
+                return html! {
+                    <VersusComponent/>
+                };
+            }
+            Some(AppRoute::Group) => {
+                return html! {
+                    <GroupComponent/>
+                };
+            }
+            Some(AppRoute::Replay) => {
+                return html! {
+                    <ReplayComponent/>
+                };
+            }
+            Some(AppRoute::Puzzle) => {
+                return html! {
+                    <PuzzleComponent/>
+                };
+            }
+            Some(AppRoute::Verify) => {
+                return html! {
+                    <VerifyComponent/>
+                };
+            }
+            Some(AppRoute::Evil) => {
+                return html! {
+                    <EvilComponent/>
+                };
+            }
+            _ => {}
         }
-        return html! {
+        html! {
             <div>
             <h1>{"Pico Bagel Fermi"}</h1>
             <p>{r"Pico, Bagel, Fermi is a code-breaking game where one player
@@ -238,6 +869,12 @@ impl Component for Model {
             <p>{"As an example, if the hidden secret is '123', your guesses may look as follows:"}</p>
             {render_guesses(&EXAMPLE_GUESS_STATE)}
             <p><a href="/hint.html">{"You can also get a hint for your next guess"}</a></p>
+            <p><a href="/daily.html">{"Play today's daily puzzle"}</a></p>
+            <p><a href="/versus.html">{"Play someone head to head"}</a></p>
+            <p><a href="/group.html">{"Race a group to crack one secret"}</a></p>
+            <p><a href="/puzzle.html">{"Solve a lock puzzle from a few clues"}</a></p>
+            <p><a href="/verify.html">{"Check the clues of a puzzle of your own"}</a></p>
+            <p><a href="/evil.html">{"Take on a codemaker that cheats"}</a></p>
                 {
                     if self.invalid_url {
                         html!{<p>{"Invalid url"}</p>}
@@ -252,44 +889,169 @@ impl Component for Model {
                         Mode::CreatedSecret(encoded_secret) => html!{<a href={format!("/?{}",encoded_secret)}>{"Share this link to have someone guess the number"}</a>},
                         Mode::CreateSecret => html!{},
                         Mode::EncryptingSecret => html!{},
-                        Mode::Guess(guess_state) => html!{
-                            <div class="guesses">
-                            <h2>{"Try to guess what the hidden secret is."}</h2>
-                                {render_guesses(guess_state)}
-                                <label for="next_guess">{"Next guess"}</label>
-                                <input type="text" id="next_guess" ref={self.next_guess_input_ref.clone()}/>
-                                <input type="submit" onclick=self.link.callback(|_|Msg::Guess)/>
-                            </div>
-                        }
+                        Mode::Guess(guess_state) => self.render_guess_view(guess_state),
                     }
                 }
 
-                <h2>{"New game"}</h2>
-                <p>{"To create a new game, enter a secret to guess below, and click \"Create new game.\""}</p>
-                <label for={"secret_number_input"}>{"Secret number"}</label>
-                <input type="text" ref={self.secret_input_ref.clone()} id={"secret_number_input"}/>
-                <input type="submit" value="Create new game" onclick=self.link.callback(|_|Msg::CreateSecret)/>
+                {self.render_new_game()}
             </div>
-        };
+        }
     }
 }
 
 fn render_guesses(guess_state: &GuessState) -> Html {
     html! {
         <ul>
-          {for guess_state.guesses.iter().map(|guess|render_guess(&guess_state.secret, guess_state.secret_length, guess))}
+          {for guess_state.guesses.iter().map(|guess|render_guess(guess_state, guess))}
         </ul>
     }
 }
 
-fn render_guess(secret: &Secret<GuessSpace>, secret_length: usize, guess: &String) -> Html {
-    let pbf_stats = secret.compare(&guess.chars().collect());
-    let success_html = if pbf_stats.f == secret_length as i32 {
-        html! {" (Correct)"}
+// Shows the outcome against each secret, in columns.
+// Colors each symbol of the guess by whether it's in the right place, elsewhere in the secret, or absent.
+fn render_positions(guess: &str, positions: &[PositionResult]) -> Html {
+    html! {
+        <span class="positions">
+            {for guess.chars().zip(positions).map(|(symbol, position)| {
+                let (class, css) = match position {
+                    PositionResult::Exact => ("exact", "#6aaa64"),
+                    PositionResult::Present => ("present", "#c9b458"),
+                    PositionResult::Absent => ("absent", "#787c7e"),
+                };
+                html! {
+                    <span class={class} style={format!("display: inline-block; min-width: 1.2em; text-align: center; margin: 0 0.1em; color: #fff; background: {};", css)}>{symbol}</span>
+                }
+            })}
+        </span>
+    }
+}
+
+fn render_guess(guess_state: &GuessState, guess: &str) -> Html {
+    let results = guess_state.compare(guess);
+    if let Feedback::Positions = guess_state.rules.feedback {
+        return html! {
+            <li>
+                {for guess_state.compare_positions(guess).iter().zip(&results).map(|(positions, pbf_stats)| {
+                    let success_html = if guess_state.is_correct(pbf_stats) {
+                        html! {" (Correct)"}
+                    } else {
+                        html! {}
+                    };
+                    html! {<span class="feedback">{render_positions(guess, positions)} {success_html} </span>}
+                })}
+            </li>
+        };
+    }
+    if let Variant::Mastermind = guess_state.rules.variant {
+        return html! {
+            <li>{render_code(guess)} {" - "}
+                {for results.iter().map(|pbf_stats| html! {<span class="feedback">{render_pegs(pbf_stats)} </span>})}
+            </li>
+        };
+    }
+    html! {
+        <li>{guess} {" - "}
+            {for results.iter().map(|pbf_stats| {
+                let success_html = if guess_state.is_correct(pbf_stats) {
+                    html! {" (Correct)"}
+                } else {
+                    html! {}
+                };
+                html! {<span class="feedback">{guess_state.format_result(pbf_stats)} {success_html} </span>}
+            })}
+        </li>
+    }
+}
+
+// One row per symbol and one column per position: a check where the symbol must be, a cross where it can't be, and blank where it might be.
+// The last column is how likely the symbol is to be in the secret at all.
+fn render_knowledge_grid(grid: &KnowledgeGrid<GuessSpace>) -> Html {
+    let length = grid.cells.first().map(|row| row.len()).unwrap_or(0);
+    html! {
+        <table class="knowledge_grid">
+            <tr>
+                <th></th>
+                {for (1..=length).map(|position| html! {<th>{position}</th>})}
+                <th>{"In secret"}</th>
+            </tr>
+            {for grid.symbols.iter().zip(&grid.cells).zip(&grid.presence).map(|((symbol, row), presence)| html! {
+                <tr>
+                    <th>{symbol}</th>
+                    {for row.iter().map(|cell| {
+                        let (class, text) = match cell {
+                            Knowledge::Impossible => ("impossible", "\u{2717}"),
+                            Knowledge::Possible => ("possible", ""),
+                            Knowledge::Certain => ("certain", "\u{2713}"),
+                        };
+                        html! {<td class={class}>{text}</td>}
+                    })}
+                    <td>{format!("{:.0}%", presence * 100.0)}</td>
+                </tr>
+            })}
+        </table>
+    }
+}
+
+fn render_summary(guess_state: &GuessState) -> Html {
+    let analysis_html = if let Some(analysis) = &guess_state.analysis {
+        html! {
+            <table>
+                <tr>
+                    <th>{"Guess"}</th>
+                    <th>{"Outcome"}</th>
+                    <th>{"Possible secrets left"}</th>
+                    <th>{"Information gained"}</th>
+                    <th>{"Solver's guess"}</th>
+                    <th>{"Solver's information gained"}</th>
+                </tr>
+                {for analysis.iter().map(|analysis| render_guess_analysis(guess_state, analysis))}
+            </table>
+        }
     } else {
+        html! {<p>{"There are too many possible secrets to grade this game."}</p>}
+    };
+    let title = match guess_state.status() {
+        GameStatus::Won => format!("Solved in {} guesses", guess_state.guesses.len()),
+        GameStatus::Lost => format!("Out of guesses after {}", guess_state.guesses.len()),
+        GameStatus::GaveUp => format!("Gave up after {} guesses", guess_state.guesses.len()),
+        GameStatus::InProgress => panic!("Summarizing a game that isn't over"),
+    };
+    let reveal_html = if guess_state.status() == GameStatus::Won {
         html! {}
+    } else {
+        html! {<p class="reveal">{format!("The answer was {}", guess_state.secret_string())}</p>}
+    };
+    let hints_html = match guess_state.hints_used {
+        0 => html! {},
+        1 => html! {<p>{"Used 1 hint"}</p>},
+        hints_used => html! {<p>{format!("Used {} hints", hints_used)}</p>},
     };
-    html! {<li>{guess} {" - "} {pbf_stats} {success_html} </li>}
+    html! {
+        <div class="summary">
+            <h2>{title}</h2>
+            {reveal_html}
+            {hints_html}
+            {analysis_html}
+        </div>
+    }
+}
+
+fn render_guess_analysis(guess_state: &GuessState, analysis: &GuessAnalysis<GuessSpace>) -> Html {
+    let best_guess: String = analysis
+        .best_guess
+        .as_ref()
+        .map(|best_guess| best_guess.iter().collect())
+        .unwrap_or_default();
+    html! {
+        <tr>
+            <td>{analysis.guess.iter().collect::<String>()}</td>
+            <td>{for analysis.results.iter().map(|result| html! {<span class="feedback">{guess_state.format_result(result)}</span>})}</td>
+            <td>{for analysis.candidates_after.iter().zip(&analysis.candidates_before).map(|(after, before)| html! {<span class="feedback">{format!("{} of {}", after, before)}</span>})}</td>
+            <td>{format!("{:.2} bits", analysis.bits_gained())}</td>
+            <td>{best_guess}</td>
+            <td>{format!("{:.2} bits", analysis.best_bits_gained())}</td>
+        </tr>
+    }
 }
 
 #[wasm_bindgen(start)]
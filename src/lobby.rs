@@ -0,0 +1,248 @@
+use crate::PbfStats;
+
+// Messages of the group mode, sent as websocket text in the same "key=value" lines as share links.
+// Unlike the head-to-head relay, the lobby server holds the secret and scores every guess itself, so guessers only ever learn how their own guesses did.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LobbyMessage {
+    // Host to server. The player id lets the host, or a guesser, who drops out come back as the same player.
+    Open {
+        lobby: String,
+        player: String,
+        secret: String,
+    },
+    // Guesser to server.
+    Join {
+        lobby: String,
+        player: String,
+        nickname: String,
+    },
+    Guess {
+        guess: String,
+    },
+
+    // Server to host and guessers, once they're in the lobby.
+    Joined {
+        length: usize,
+    },
+    // Server to the guesser who made the guess. Every earlier guess is sent again after rejoining.
+    Scored {
+        guess: String,
+        stats: PbfStats,
+    },
+    // Server to everyone whenever it changes.
+    Leaderboard {
+        entries: Vec<LeaderboardEntry>,
+    },
+    // Server to everyone once someone cracks the secret. Nobody sees anyone else's guesses until then.
+    Finished {
+        winner: String,
+        secret: String,
+        guesses: Vec<PlayerGuesses>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeaderboardEntry {
+    pub nickname: String,
+    pub guesses: usize,
+    // None until the player has guessed.
+    pub best: Option<PbfStats>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerGuesses {
+    pub nickname: String,
+    pub guesses: Vec<(String, PbfStats)>,
+}
+
+// Closer to the secret: more in the right place first, then more elsewhere in the secret.
+pub fn is_better(stats: PbfStats, than: PbfStats) -> bool {
+    (stats.f, stats.p) > (than.f, than.p)
+}
+
+// Best result first, then whoever needed fewer guesses to get it.
+pub fn sort_leaderboard(entries: &mut [LeaderboardEntry]) {
+    entries.sort_by_key(|entry| {
+        let best = entry.best.map(|best| (best.f, best.p));
+        (std::cmp::Reverse(best), entry.guesses)
+    });
+}
+
+fn field<'a>(fields: &[(&str, &'a str)], key: &str) -> Result<&'a str, String> {
+    fields
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, value)| *value)
+        .ok_or(format!("Missing {}", key))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_ignored| format!("{} is not a number", value))
+}
+
+fn number_field<T: std::str::FromStr>(fields: &[(&str, &str)], key: &str) -> Result<T, String> {
+    parse_number(field(fields, key)?)
+}
+
+// Nicknames and guesses go last, so they can contain commas.
+fn encode_scored(key: &str, guess: &str, stats: PbfStats) -> String {
+    format!("{}={},{},{}\n", key, stats.p, stats.f, guess)
+}
+
+fn decode_scored(value: &str) -> Result<(String, PbfStats), String> {
+    let mut parts = value.splitn(3, ',');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(p), Some(f), Some(guess)) => Ok((
+            guess.to_string(),
+            PbfStats {
+                p: parse_number(p)?,
+                f: parse_number(f)?,
+            },
+        )),
+        _ => Err(format!("{} is not a scored guess", value)),
+    }
+}
+
+impl LeaderboardEntry {
+    fn encode(&self) -> String {
+        let best = self.best.unwrap_or(PbfStats { p: 0, f: 0 });
+        format!(
+            "player={},{},{},{}\n",
+            self.guesses, best.p, best.f, self.nickname
+        )
+    }
+
+    fn decode(value: &str) -> Result<Self, String> {
+        let (guesses, scored) = value
+            .split_once(',')
+            .ok_or(format!("{} is not a leaderboard entry", value))?;
+        let guesses = parse_number(guesses)?;
+        let (nickname, best) = decode_scored(scored)?;
+        Ok(Self {
+            nickname,
+            guesses,
+            best: if guesses == 0 { None } else { Some(best) },
+        })
+    }
+}
+
+impl LobbyMessage {
+    pub fn encode(&self) -> String {
+        match self {
+            LobbyMessage::Open {
+                lobby,
+                player,
+                secret,
+            } => format!(
+                "kind=open\nlobby={}\nplayer={}\nsecret={}\n",
+                lobby, player, secret
+            ),
+            LobbyMessage::Join {
+                lobby,
+                player,
+                nickname,
+            } => format!(
+                "kind=join\nlobby={}\nplayer={}\nnickname={}\n",
+                lobby, player, nickname
+            ),
+            LobbyMessage::Guess { guess } => format!("kind=guess\nguess={}\n", guess),
+            LobbyMessage::Joined { length } => format!("kind=joined\nlength={}\n", length),
+            LobbyMessage::Scored { guess, stats } => {
+                format!("kind=scored\n{}", encode_scored("guess", guess, *stats))
+            }
+            LobbyMessage::Leaderboard { entries } => {
+                let mut encoded = "kind=leaderboard\n".to_string();
+                for entry in entries {
+                    encoded.push_str(&entry.encode());
+                }
+                encoded
+            }
+            LobbyMessage::Finished {
+                winner,
+                secret,
+                guesses,
+            } => {
+                let mut encoded = format!("kind=finished\nwinner={}\nsecret={}\n", winner, secret);
+                // Each player's guesses follow their nickname.
+                for player in guesses {
+                    encoded.push_str(&format!("nickname={}\n", player.nickname));
+                    for (guess, stats) in &player.guesses {
+                        encoded.push_str(&encode_scored("guess", guess, *stats));
+                    }
+                }
+                encoded
+            }
+            LobbyMessage::Error { message } => format!("kind=error\nmessage={}\n", message),
+        }
+    }
+
+    pub fn decode(s: &str) -> Result<Self, String> {
+        let fields: Vec<(&str, &str)> = s
+            .lines()
+            .map(|line| match line.find('=') {
+                Some(i) => Ok((&line[..i], &line[i + 1..])),
+                None => Err(format!("{} is not a key=value line", line)),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(match field(&fields, "kind")? {
+            "open" => LobbyMessage::Open {
+                lobby: field(&fields, "lobby")?.to_string(),
+                player: field(&fields, "player")?.to_string(),
+                secret: field(&fields, "secret")?.to_string(),
+            },
+            "join" => LobbyMessage::Join {
+                lobby: field(&fields, "lobby")?.to_string(),
+                player: field(&fields, "player")?.to_string(),
+                nickname: field(&fields, "nickname")?.to_string(),
+            },
+            "guess" => LobbyMessage::Guess {
+                guess: field(&fields, "guess")?.to_string(),
+            },
+            "joined" => LobbyMessage::Joined {
+                length: number_field(&fields, "length")?,
+            },
+            "scored" => {
+                let (guess, stats) = decode_scored(field(&fields, "guess")?)?;
+                LobbyMessage::Scored { guess, stats }
+            }
+            "leaderboard" => LobbyMessage::Leaderboard {
+                entries: fields
+                    .iter()
+                    .filter(|(key, _)| *key == "player")
+                    .map(|(_, value)| LeaderboardEntry::decode(value))
+                    .collect::<Result<_, _>>()?,
+            },
+            "finished" => {
+                let mut guesses: Vec<PlayerGuesses> = Vec::new();
+                for (key, value) in &fields {
+                    match *key {
+                        "nickname" => guesses.push(PlayerGuesses {
+                            nickname: value.to_string(),
+                            guesses: Vec::new(),
+                        }),
+                        "guess" => guesses
+                            .last_mut()
+                            .ok_or("Guesses must follow a nickname")?
+                            .guesses
+                            .push(decode_scored(value)?),
+                        _ => {}
+                    }
+                }
+                LobbyMessage::Finished {
+                    winner: field(&fields, "winner")?.to_string(),
+                    secret: field(&fields, "secret")?.to_string(),
+                    guesses,
+                }
+            }
+            "error" => LobbyMessage::Error {
+                message: field(&fields, "message")?.to_string(),
+            },
+            kind => return Err(format!("Unknown message kind {}", kind)),
+        })
+    }
+}
@@ -0,0 +1,147 @@
+use crate::PbfStats;
+use yew::{html, Callback, Component, ComponentLink, Html, Properties};
+
+pub struct Color {
+    // How the color is written in secrets and share links.
+    pub symbol: char,
+    pub name: &'static str,
+    pub css: &'static str,
+}
+
+pub const COLORS: [Color; 6] = [
+    Color {
+        symbol: 'R',
+        name: "Red",
+        css: "#d32f2f",
+    },
+    Color {
+        symbol: 'O',
+        name: "Orange",
+        css: "#f57c00",
+    },
+    Color {
+        symbol: 'Y',
+        name: "Yellow",
+        css: "#fbc02d",
+    },
+    Color {
+        symbol: 'G',
+        name: "Green",
+        css: "#388e3c",
+    },
+    Color {
+        symbol: 'B',
+        name: "Blue",
+        css: "#1976d2",
+    },
+    Color {
+        symbol: 'P',
+        name: "Purple",
+        css: "#7b1fa2",
+    },
+];
+
+pub const CODE_LENGTH: usize = 4;
+
+fn color_for(symbol: char) -> Option<&'static Color> {
+    COLORS.iter().find(|color| color.symbol == symbol)
+}
+
+fn render_color(symbol: char) -> Html {
+    match color_for(symbol) {
+        Some(color) => html! {
+            <span class="code-peg" title={color.name} style={format!("display: inline-block; width: 1.5em; height: 1.5em; border-radius: 50%; margin: 0 0.1em; background: {};", color.css)}></span>
+        },
+        None => html! {<span>{symbol}</span>},
+    }
+}
+
+pub fn render_code(code: &str) -> Html {
+    html! {
+        <span class="code">{for code.chars().map(render_color)}</span>
+    }
+}
+
+fn render_key_peg(css: &str) -> Html {
+    html! {
+        <span class="key-peg" style={format!("display: inline-block; width: 0.6em; height: 0.6em; border-radius: 50%; margin: 0 0.1em; border: 1px solid #000; background: {};", css)}></span>
+    }
+}
+
+// A black key peg for each 'f' and a white one for each 'p', the way Mastermind shows feedback.
+pub fn render_pegs(stats: &PbfStats) -> Html {
+    html! {
+        <span class="key-pegs" title={format!("{}", stats)}>
+            {for (0..stats.f).map(|_| render_key_peg("#000"))}
+            {for (0..stats.p).map(|_| render_key_peg("#fff"))}
+        </span>
+    }
+}
+
+// Lets the guesser build a code by clicking colors, in place of typing into a text box.
+pub struct ColorPicker {
+    link: ComponentLink<Self>,
+    props: Props,
+    picked: Vec<char>,
+}
+
+#[derive(Properties, Clone)]
+pub struct Props {
+    pub on_guess: Callback<String>,
+}
+
+pub enum Msg {
+    Pick(char),
+    RemoveLast,
+    Submit,
+}
+
+impl Component for ColorPicker {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            link,
+            props,
+            picked: Vec::new(),
+        }
+    }
+    fn update(&mut self, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Pick(symbol) => {
+                if self.picked.len() < CODE_LENGTH {
+                    self.picked.push(symbol);
+                }
+            }
+            Msg::RemoveLast => {
+                self.picked.pop();
+            }
+            Msg::Submit => {
+                if self.picked.len() == CODE_LENGTH {
+                    self.props.on_guess.emit(self.picked.drain(..).collect());
+                }
+            }
+        }
+        true
+    }
+    fn change(&mut self, props: Self::Properties) -> bool {
+        self.props = props;
+        false
+    }
+    fn view(&self) -> Html {
+        html! {
+            <div class="color-picker">
+                <p>{render_code(&self.picked.iter().collect::<String>())}</p>
+                {for COLORS.iter().map(|color| {
+                    let symbol = color.symbol;
+                    html! {
+                        <button title={color.name} style={format!("background: {};", color.css)} onclick=self.link.callback(move |_| Msg::Pick(symbol))>{symbol}</button>
+                    }
+                })}
+                <button onclick=self.link.callback(|_| Msg::RemoveLast)>{"Undo"}</button>
+                <input type="submit" value="Guess" disabled={self.picked.len() != CODE_LENGTH} onclick=self.link.callback(|_| Msg::Submit)/>
+            </div>
+        }
+    }
+}
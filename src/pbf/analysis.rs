@@ -0,0 +1,96 @@
+use crate::pbf::solver::{Guess, MultiGuessState};
+use crate::secret::Secret;
+use crate::PbfStats;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+// How much a single guess narrowed down the secrets, next to what the solver would have guessed at the same point.
+// The per-secret fields are in the same order as the secrets.
+pub struct GuessAnalysis<T> {
+    pub guess: Vec<T>,
+    pub results: Vec<PbfStats>,
+
+    // Number of values each secret could still have before and after this guess.
+    pub candidates_before: Vec<usize>,
+    pub candidates_after: Vec<usize>,
+
+    // The solver's pick given the guesses made so far, and how many values each secret would have had left had it been guessed instead.
+    pub best_guess: Option<Vec<T>>,
+    pub best_candidates_after: Vec<usize>,
+}
+
+// The secrets are independent, so the information gained about each of them adds up.
+fn bits(before: &[usize], after: &[usize]) -> f64 {
+    before
+        .iter()
+        .zip(after)
+        .filter(|(_, after)| **after != 0)
+        .map(|(before, after)| (*before as f64 / *after as f64).log2())
+        .sum()
+}
+
+impl<T> GuessAnalysis<T> {
+    // Information gained by the guess that was actually made, in bits.
+    pub fn bits_gained(&self) -> f64 {
+        bits(&self.candidates_before, &self.candidates_after)
+    }
+
+    // Information the solver's guess would have gained against the same secrets, in bits.
+    pub fn best_bits_gained(&self) -> f64 {
+        bits(&self.candidates_before, &self.best_candidates_after)
+    }
+}
+
+// Replays the guesses against the secrets one at a time, grading each one against the solver's best guess at that point.
+// The state holds the candidates for each secret before any guesses were made.
+pub fn analyze_game<T>(
+    secrets: &[Secret<T>],
+    guesses: &[Vec<T>],
+    mut state: MultiGuessState<T>,
+) -> Vec<GuessAnalysis<T>>
+where
+    T: Clone + Eq + Hash + Debug,
+{
+    let mut analyses = Vec::with_capacity(guesses.len());
+    for guess in guesses {
+        let candidates_before = candidate_counts(&state);
+        let best_guess = state.next_guess();
+        let best_candidates_after = match &best_guess {
+            Some(best_guess) => secrets
+                .iter()
+                .zip(state.states())
+                .map(|(secret, secret_state)| {
+                    secret_state.count_consistent(&secret_state.score(secret, best_guess.clone()))
+                })
+                .collect(),
+            None => candidates_before.clone(),
+        };
+        let scored: Vec<Guess<T>> = secrets
+            .iter()
+            .zip(state.states())
+            .map(|(secret, secret_state)| secret_state.score(secret, guess.clone()))
+            .collect();
+        let results: Vec<PbfStats> = scored.iter().map(|scored| scored.result).collect();
+        state.add_guess(scored);
+        analyses.push(GuessAnalysis {
+            guess: guess.clone(),
+            results,
+            candidates_before,
+            candidates_after: candidate_counts(&state),
+            best_guess,
+            best_candidates_after,
+        });
+    }
+    analyses
+}
+
+fn candidate_counts<T>(state: &MultiGuessState<T>) -> Vec<usize>
+where
+    T: Clone + Eq + Hash + Debug,
+{
+    state
+        .states()
+        .iter()
+        .map(|secret_state| secret_state.candidate_count())
+        .collect()
+}
@@ -0,0 +1,425 @@
+use crate::pbf::{PbfStats, PositionResult, Scoring};
+
+// Candidates are stored as packed codes rather than one Secret (and HashSet) each. Every symbol is replaced by its index in the guess space, and the indices are the digits of a number written in base guess_space.len().
+pub type Code = u64;
+
+// Longest secret that can be packed.
+pub const MAX_LENGTH: usize = 16;
+
+// Symbol indices of a single guess or candidate. Only the first `length` are used.
+pub type Symbols = [u8; MAX_LENGTH];
+
+// Stands in for a symbol that isn't in the guess space, so it never matches anything.
+pub const UNKNOWN_SYMBOL: u8 = u8::MAX;
+
+// Guess spaces up to this size are compared with a bitmask and an array of counts on the stack instead of searching the secret for every guess symbol. That covers digits, colors and letters.
+pub const SMALL_ALPHABET: usize = 64;
+
+#[derive(Clone, Copy)]
+pub struct Codec {
+    base: u64,
+    length: usize,
+}
+
+impl Codec {
+    pub fn new(base: usize, length: usize) -> Self {
+        assert!(
+            base < UNKNOWN_SYMBOL as usize,
+            "Guess space of {} symbols is too big",
+            base
+        );
+        assert!(length <= MAX_LENGTH, "Length {} is too long", length);
+        Self {
+            base: base as u64,
+            length,
+        }
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    // How many different codes there are, if that fits in a usize.
+    pub fn size(&self) -> Option<usize> {
+        (self.base as usize).checked_pow(self.length as u32)
+    }
+
+    // None if a symbol isn't in the guess space.
+    pub fn encode(&self, symbols: &Symbols) -> Option<Code> {
+        let mut code: Code = 0;
+        for symbol in symbols[..self.length].iter().rev() {
+            if *symbol as u64 >= self.base {
+                return None;
+            }
+            code = code * self.base + *symbol as u64;
+        }
+        Some(code)
+    }
+
+    pub fn compare(&self, secret: &Symbols, guess: &Symbols, scoring: Scoring) -> PbfStats {
+        if self.base as usize <= SMALL_ALPHABET {
+            compare_small_symbols(secret, guess, self.length, scoring)
+        } else {
+            compare_symbols(secret, guess, self.length, scoring)
+        }
+    }
+
+    pub fn compare_positions(&self, secret: &Symbols, guess: &Symbols) -> Vec<PositionResult> {
+        if self.base as usize <= SMALL_ALPHABET {
+            compare_small_symbol_positions(secret, guess, self.length)
+        } else {
+            compare_symbol_positions(secret, guess, self.length)
+        }
+    }
+
+    pub fn decode(&self, mut code: Code) -> Symbols {
+        let mut symbols = [0; MAX_LENGTH];
+        for symbol in symbols[..self.length].iter_mut() {
+            *symbol = (code % self.base) as u8;
+            code /= self.base;
+        }
+        symbols
+    }
+}
+
+// Works with any guess space.
+pub fn compare_symbols(
+    secret: &Symbols,
+    guess: &Symbols,
+    length: usize,
+    scoring: Scoring,
+) -> PbfStats {
+    let f = (0..length).filter(|i| secret[*i] == guess[*i]).count();
+    let matched = match scoring {
+        Scoring::Set => guess[..length]
+            .iter()
+            .filter(|symbol| secret[..length].contains(symbol))
+            .count(),
+        Scoring::Multiset => {
+            let mut used = [false; MAX_LENGTH];
+            guess[..length]
+                .iter()
+                .filter(
+                    |symbol| match (0..length).find(|i| !used[*i] && secret[*i] == **symbol) {
+                        Some(i) => {
+                            used[i] = true;
+                            true
+                        }
+                        None => false,
+                    },
+                )
+                .count()
+        }
+    };
+    PbfStats {
+        f: f as i32,
+        p: (matched - f) as i32,
+    }
+}
+
+// Same as compare_symbols, for guess spaces no bigger than SMALL_ALPHABET.
+pub fn compare_small_symbols(
+    secret: &Symbols,
+    guess: &Symbols,
+    length: usize,
+    scoring: Scoring,
+) -> PbfStats {
+    let mut f = 0;
+    let mut matched = 0;
+    match scoring {
+        Scoring::Set => {
+            let present = secret[..length]
+                .iter()
+                .fold(0u64, |present, symbol| present | 1 << symbol);
+            for i in 0..length {
+                if secret[i] == guess[i] {
+                    f += 1;
+                }
+                if (guess[i] as usize) < SMALL_ALPHABET && present & 1 << guess[i] != 0 {
+                    matched += 1;
+                }
+            }
+        }
+        Scoring::Multiset => {
+            let mut unmatched = [0u8; SMALL_ALPHABET];
+            for symbol in &secret[..length] {
+                unmatched[*symbol as usize] += 1;
+            }
+            for i in 0..length {
+                if secret[i] == guess[i] {
+                    f += 1;
+                }
+                if let Some(count) = unmatched.get_mut(guess[i] as usize) {
+                    if *count > 0 {
+                        *count -= 1;
+                        matched += 1;
+                    }
+                }
+            }
+        }
+    }
+    PbfStats { f, p: matched - f }
+}
+
+// Same rules as Secret::compare_positions. Works with any guess space.
+pub fn compare_symbol_positions(
+    secret: &Symbols,
+    guess: &Symbols,
+    length: usize,
+) -> Vec<PositionResult> {
+    let mut used = [false; MAX_LENGTH];
+    for i in 0..length {
+        used[i] = secret[i] == guess[i];
+    }
+    (0..length)
+        .map(|i| {
+            if secret[i] == guess[i] {
+                return PositionResult::Exact;
+            }
+            match (0..length).find(|j| !used[*j] && secret[*j] == guess[i]) {
+                Some(j) => {
+                    used[j] = true;
+                    PositionResult::Present
+                }
+                None => PositionResult::Absent,
+            }
+        })
+        .collect()
+}
+
+// Same as compare_symbol_positions, for guess spaces no bigger than SMALL_ALPHABET.
+pub fn compare_small_symbol_positions(
+    secret: &Symbols,
+    guess: &Symbols,
+    length: usize,
+) -> Vec<PositionResult> {
+    let mut unmatched = [0u8; SMALL_ALPHABET];
+    for i in 0..length {
+        if secret[i] != guess[i] {
+            unmatched[secret[i] as usize] += 1;
+        }
+    }
+    (0..length)
+        .map(|i| {
+            if secret[i] == guess[i] {
+                return PositionResult::Exact;
+            }
+            match unmatched.get_mut(guess[i] as usize) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    PositionResult::Present
+                }
+                _ => PositionResult::Absent,
+            }
+        })
+        .collect()
+}
+
+// The codes that could still be the secret, as one bit per possible code.
+pub struct CandidateSet {
+    // Sorted codes that can be candidates at all, or None when every code below `size` can be.
+    list: Option<Vec<Code>>,
+    size: usize,
+    alive: Vec<u64>,
+    alive_count: usize,
+}
+
+impl CandidateSet {
+    // Every code below size.
+    pub fn all(size: usize) -> Self {
+        Self::with_size(None, size)
+    }
+
+    pub fn from_list(mut list: Vec<Code>) -> Self {
+        list.sort_unstable();
+        list.dedup();
+        let size = list.len();
+        Self::with_size(Some(list), size)
+    }
+
+    fn with_size(list: Option<Vec<Code>>, size: usize) -> Self {
+        let mut alive = vec![u64::MAX; size.div_ceil(64)];
+        if !size.is_multiple_of(64) {
+            if let Some(last) = alive.last_mut() {
+                *last = (1 << (size % 64)) - 1;
+            }
+        }
+        Self {
+            list,
+            size,
+            alive,
+            alive_count: size,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.alive_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alive_count == 0
+    }
+
+    fn code(&self, slot: usize) -> Code {
+        match &self.list {
+            Some(list) => list[slot],
+            None => slot as Code,
+        }
+    }
+
+    fn slot(&self, code: Code) -> Option<usize> {
+        match &self.list {
+            Some(list) => list.binary_search(&code).ok(),
+            None => Some(code as usize).filter(|slot| *slot < self.size),
+        }
+    }
+
+    pub fn contains(&self, code: Code) -> bool {
+        self.slot(code)
+            .map(|slot| self.alive[slot / 64] & (1 << (slot % 64)) != 0)
+            .unwrap_or(false)
+    }
+
+    fn alive_slots(&self) -> impl Iterator<Item = usize> + '_ {
+        self.alive
+            .iter()
+            .enumerate()
+            .flat_map(|(word_index, word)| {
+                let mut word = *word;
+                std::iter::from_fn(move || {
+                    if word == 0 {
+                        None
+                    } else {
+                        let bit = word.trailing_zeros() as usize;
+                        word &= word - 1;
+                        Some(word_index * 64 + bit)
+                    }
+                })
+            })
+    }
+
+    // The codes that are still candidates, lazily.
+    pub fn iter(&self) -> impl Iterator<Item = Code> + '_ {
+        self.alive_slots().map(move |slot| self.code(slot))
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(Code) -> bool) {
+        for word_index in 0..self.alive.len() {
+            let mut remaining = self.alive[word_index];
+            while remaining != 0 {
+                let bit = remaining.trailing_zeros() as usize;
+                remaining &= remaining - 1;
+                if !keep(self.code(word_index * 64 + bit)) {
+                    self.alive[word_index] &= !(1 << bit);
+                    self.alive_count -= 1;
+                }
+            }
+        }
+    }
+
+    // At most max candidates, spread evenly over all of them.
+    pub fn sample(&self, max: usize) -> Vec<Code> {
+        let stride = self.alive_count.div_ceil(max.max(1)).max(1);
+        self.iter().step_by(stride).collect()
+    }
+
+    // At most max codes that could ever be candidates, alive or not, spread evenly over all of them.
+    pub fn sample_all(&self, max: usize) -> impl Iterator<Item = Code> + '_ {
+        let stride = self.size.div_ceil(max.max(1)).max(1);
+        (0..self.size)
+            .step_by(stride)
+            .map(move |slot| self.code(slot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret::Secret;
+
+    #[test]
+    fn codes_decode_to_what_was_encoded() {
+        let codec = Codec::new(6, 4);
+        assert_eq!(codec.size(), Some(1296));
+        for code in 0..codec.size().unwrap() as Code {
+            let symbols = codec.decode(code);
+            assert!(symbols[..4].iter().all(|symbol| *symbol < 6));
+            assert_eq!(codec.encode(&symbols), Some(code));
+        }
+    }
+
+    #[test]
+    fn symbols_outside_the_guess_space_dont_encode() {
+        let codec = Codec::new(6, 4);
+        let mut symbols = codec.decode(0);
+        symbols[2] = 6;
+        assert_eq!(codec.encode(&symbols), None);
+        symbols[2] = UNKNOWN_SYMBOL;
+        assert_eq!(codec.encode(&symbols), None);
+    }
+
+    #[test]
+    fn symbol_comparisons_match_secret() {
+        let codec = Codec::new(4, 4);
+        let size = codec.size().unwrap() as Code;
+        for scoring in [Scoring::Set, Scoring::Multiset] {
+            for secret_code in 0..size {
+                let secret_symbols = codec.decode(secret_code);
+                let secret = Secret::new(secret_symbols[..4].to_vec());
+                for guess_code in 0..size {
+                    let guess = codec.decode(guess_code);
+                    let expected = secret.compare_with(&guess[..4], scoring);
+                    assert_eq!(
+                        compare_symbols(&secret_symbols, &guess, 4, scoring),
+                        expected
+                    );
+                    assert_eq!(
+                        compare_small_symbols(&secret_symbols, &guess, 4, scoring),
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn retain_drops_candidates_and_keeps_count() {
+        let mut candidates = CandidateSet::all(200);
+        assert_eq!(candidates.len(), 200);
+        candidates.retain(|code| code % 3 == 0);
+        assert_eq!(candidates.len(), 67);
+        assert!(candidates.iter().all(|code| code % 3 == 0));
+        assert!(candidates.contains(198));
+        assert!(!candidates.contains(199));
+        candidates.retain(|code| code >= 150);
+        assert_eq!(
+            candidates.iter().collect::<Vec<_>>(),
+            (150..200).step_by(3).collect::<Vec<_>>()
+        );
+        assert_eq!(candidates.len(), 17);
+        candidates.retain(|_| false);
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn retain_works_on_listed_codes() {
+        let mut candidates = CandidateSet::from_list(vec![900, 5, 70, 5, 3000]);
+        assert_eq!(candidates.len(), 4);
+        candidates.retain(|code| code != 70);
+        assert_eq!(candidates.iter().collect::<Vec<_>>(), vec![5, 900, 3000]);
+        assert!(!candidates.contains(70));
+    }
+
+    #[test]
+    fn sample_is_spread_over_live_candidates() {
+        let mut candidates = CandidateSet::all(1000);
+        candidates.retain(|code| code % 2 == 1);
+        let sample = candidates.sample(100);
+        assert_eq!(sample.len(), 100);
+        assert_eq!(sample.first(), Some(&1));
+        assert!(sample.last().unwrap() > &900);
+        assert!(sample.iter().all(|code| code % 2 == 1));
+        assert_eq!(candidates.sample(1000).len(), 500);
+        assert!(CandidateSet::all(0).sample(10).is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+use crate::pbf::solver::Guess;
+use std::fmt::Display;
+
+// Derived constraints can multiply quickly, and by then the explanation has stopped being readable anyway.
+const MAX_CONSTRAINTS: usize = 100;
+
+// How many of these symbols are in the secret, and why we know that.
+struct Constraint<T> {
+    symbols: Vec<T>,
+    count: usize,
+    reason: String,
+}
+
+fn list<T: Display>(symbols: &[T]) -> String {
+    let symbols: Vec<String> = symbols.iter().map(|symbol| symbol.to_string()).collect();
+    match symbols.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => symbols.join(""),
+    }
+}
+
+fn count_word(count: usize) -> String {
+    match count {
+        0 => "none".to_string(),
+        1 => "one".to_string(),
+        2 => "two".to_string(),
+        3 => "three".to_string(),
+        count => count.to_string(),
+    }
+}
+
+fn ordinal(position: usize) -> String {
+    match position {
+        0 => "first".to_string(),
+        1 => "second".to_string(),
+        2 => "third".to_string(),
+        3 => "fourth".to_string(),
+        4 => "fifth".to_string(),
+        position => format!("number {}", position + 1),
+    }
+}
+
+fn describe<T: Display>(guess: &Guess<T>) -> String {
+    let symbols: String = guess
+        .guess
+        .iter()
+        .map(|symbol| symbol.to_string())
+        .collect();
+    format!("{} = {}", symbols, guess.result)
+}
+
+fn has_repeats<T: Eq>(symbols: &[T]) -> bool {
+    (1..symbols.len()).any(|i| symbols[..i].contains(&symbols[i]))
+}
+
+// Whether two lists of different symbols hold the same ones, in any order.
+fn same_symbols<T: Eq>(a: &[T], b: &[T]) -> bool {
+    a.len() == b.len() && a.iter().all(|symbol| b.contains(symbol))
+}
+
+// Explains what the guesses so far say about the secret, one statement per conclusion, in the order they can be reached.
+// Works on which symbols are present, using the fact that when a guess has no repeated symbols its p plus f is how many of its symbols are in the secret, whatever the scoring. Rules are tried in this order until none of them teaches anything new:
+// 1. Symbols of a guess (or a group worked out below) that has none left to find are eliminated.
+// 2. Symbols of a guess or group that are all needed to make up its count are in the secret.
+// 3. Two guesses or groups that share symbols can pin down how many of the shared ones are present. When one has every symbol of the other, the extra symbols have whatever count is left over.
+// Then it adds what the f and p counts say about positions.
+pub fn explain<T: Clone + Eq + Display>(guesses: &[Guess<T>]) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut absent: Vec<T> = Vec::new();
+    let mut present: Vec<T> = Vec::new();
+    let mut constraints: Vec<Constraint<T>> = guesses
+        .iter()
+        .filter(|guess| !has_repeats(&guess.guess))
+        .map(|guess| Constraint {
+            symbols: guess.guess.clone(),
+            count: (guess.result.p + guess.result.f) as usize,
+            reason: describe(guess),
+        })
+        .collect();
+
+    loop {
+        // Take out what's already known, so each constraint is only about the symbols still in doubt.
+        for constraint in constraints.iter_mut() {
+            let before = constraint.symbols.len();
+            let found = constraint
+                .symbols
+                .iter()
+                .filter(|symbol| present.contains(symbol))
+                .count();
+            constraint
+                .symbols
+                .retain(|symbol| !absent.contains(symbol) && !present.contains(symbol));
+            constraint.count = constraint.count.saturating_sub(found);
+            if constraint.symbols.len() != before && !constraint.reason.ends_with("what's above") {
+                constraint.reason = format!("{}, given what's above", constraint.reason);
+            }
+        }
+        constraints.retain(|constraint| !constraint.symbols.is_empty());
+
+        // Rules 1 and 2.
+        if let Some(constraint) = constraints.iter().find(|constraint| {
+            constraint.count == 0 || constraint.count == constraint.symbols.len()
+        }) {
+            let symbols = &constraint.symbols;
+            if constraint.count == 0 {
+                let verb = if symbols.len() == 1 { "is" } else { "are" };
+                statements.push(format!(
+                    "{} {} eliminated because {}",
+                    list(symbols),
+                    verb,
+                    constraint.reason
+                ));
+                absent.extend(symbols.iter().cloned());
+            } else {
+                let subject = match symbols.len() {
+                    1 => format!("{} is", list(symbols)),
+                    2 => format!("Both {} are", list(symbols)),
+                    _ => format!("All of {} are", list(symbols)),
+                };
+                statements.push(format!(
+                    "{} in the secret because {}",
+                    subject, constraint.reason
+                ));
+                present.extend(symbols.iter().cloned());
+            }
+            continue;
+        }
+
+        // Rule 3: the shared symbols have at least what each side needs beyond its own other symbols, and at most what either side has.
+        if constraints.len() >= MAX_CONSTRAINTS {
+            break;
+        }
+        let mut derived = None;
+        'pairs: for (i, a) in constraints.iter().enumerate() {
+            for b in constraints.iter().skip(i + 1) {
+                let shared: Vec<T> = a
+                    .symbols
+                    .iter()
+                    .filter(|symbol| b.symbols.contains(symbol))
+                    .cloned()
+                    .collect();
+                if shared.is_empty()
+                    || (shared.len() == a.symbols.len() && shared.len() == b.symbols.len())
+                {
+                    continue;
+                }
+                if shared.len() == a.symbols.len() || shared.len() == b.symbols.len() {
+                    let (small, big) = if shared.len() == a.symbols.len() {
+                        (a, b)
+                    } else {
+                        (b, a)
+                    };
+                    let extra: Vec<T> = big
+                        .symbols
+                        .iter()
+                        .filter(|symbol| !shared.contains(symbol))
+                        .cloned()
+                        .collect();
+                    if big.count < small.count
+                        || constraints
+                            .iter()
+                            .any(|constraint| same_symbols(&constraint.symbols, &extra))
+                    {
+                        continue;
+                    }
+                    let count = big.count - small.count;
+                    let reason = format!("{} and {}", big.reason, small.reason);
+                    // Rules 1 and 2 say it better when they apply, and they will next time round.
+                    if count != 0 && count != extra.len() {
+                        statements.push(format!(
+                            "Exactly {} of {} {} present, from {}",
+                            count_word(count),
+                            list(&extra),
+                            if count == 1 { "is" } else { "are" },
+                            reason
+                        ));
+                    }
+                    derived = Some(vec![Constraint {
+                        symbols: extra,
+                        count,
+                        reason,
+                    }]);
+                    break 'pairs;
+                }
+                let lowest = a
+                    .count
+                    .saturating_sub(a.symbols.len() - shared.len())
+                    .max(b.count.saturating_sub(b.symbols.len() - shared.len()));
+                let highest = a.count.min(b.count).min(shared.len());
+                if lowest != highest
+                    || constraints
+                        .iter()
+                        .any(|constraint| same_symbols(&constraint.symbols, &shared))
+                {
+                    continue;
+                }
+                let statement = format!(
+                    "exactly {} of {} {} present",
+                    count_word(lowest),
+                    list(&shared),
+                    if lowest == 1 { "is" } else { "are" }
+                );
+                statements.push(format!(
+                    "{}{}, from {} and {}",
+                    statement[..1].to_uppercase(),
+                    &statement[1..],
+                    a.reason,
+                    b.reason
+                ));
+                let rest = |side: &Constraint<T>| Constraint {
+                    symbols: side
+                        .symbols
+                        .iter()
+                        .filter(|symbol| !shared.contains(symbol))
+                        .cloned()
+                        .collect(),
+                    count: side.count - lowest,
+                    reason: format!("{} and {}", side.reason, statement),
+                };
+                derived = Some(vec![
+                    rest(a),
+                    rest(b),
+                    Constraint {
+                        symbols: shared.clone(),
+                        count: lowest,
+                        reason: statement.clone(),
+                    },
+                ]);
+                break 'pairs;
+            }
+        }
+        match derived {
+            Some(derived) => constraints.extend(derived),
+            None => break,
+        }
+    }
+
+    // What the f and p counts say about where the symbols go.
+    for guess in guesses {
+        let in_secret: Vec<(usize, &T)> = guess
+            .guess
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| !absent.contains(symbol))
+            .collect();
+        if in_secret.is_empty() || guess.result.p + guess.result.f == 0 {
+            continue;
+        }
+        let places: Vec<String> = in_secret
+            .iter()
+            .map(|(position, symbol)| format!("{} isn't {}", symbol, ordinal(*position)))
+            .collect();
+        if guess.result.f == 0 {
+            statements.push(format!(
+                "{}, because {} has no f",
+                list(&places),
+                describe(guess)
+            ));
+        } else if guess.result.p == 0 && !has_repeats(&guess.guess) {
+            // With a repeated symbol under multiset scoring, a copy of it that wasn't where it was guessed can go unmatched rather than count as a p.
+            let places: Vec<String> = in_secret
+                .iter()
+                .map(|(position, symbol)| format!("{} {}", symbol, ordinal(*position)))
+                .collect();
+            statements.push(format!(
+                "Whichever of {} are in the secret are where they were guessed ({}), because {} has no p",
+                list(
+                    &in_secret
+                        .iter()
+                        .map(|(_, symbol)| (*symbol).clone())
+                        .collect::<Vec<T>>()
+                ),
+                places.join(", "),
+                describe(guess)
+            ));
+        }
+    }
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pbf::PbfStats;
+
+    fn guess(guess: &str, result: &str) -> Guess<char> {
+        Guess {
+            guess: guess.chars().collect(),
+            result: result.parse::<PbfStats>().unwrap(),
+            positions: None,
+        }
+    }
+
+    #[test]
+    fn guess_with_nothing_found_eliminates_its_symbols() {
+        let statements = explain(&[guess("789", "b")]);
+        assert_eq!(
+            statements,
+            vec!["7, 8 and 9 are eliminated because 789 = b"]
+        );
+    }
+
+    #[test]
+    fn overlapping_guesses_count_their_shared_symbols() {
+        let statements = explain(&[guess("345", "p"), guess("234", "pp")]);
+        assert_eq!(
+            statements[0],
+            "Exactly one of 3 and 4 is present, from 345 = p and 234 = pp"
+        );
+    }
+
+    #[test]
+    fn guess_containing_another_finds_the_difference() {
+        let statements = explain(&[guess("12", "p"), guess("123", "pp")]);
+        assert_eq!(
+            statements[0],
+            "3 is in the secret because 123 = pp and 12 = p"
+        );
+        let statements = explain(&[guess("123", "pp"), guess("21", "p")]);
+        assert!(statements[0].starts_with("3 is in the secret"));
+    }
+
+    #[test]
+    fn groups_already_known_in_another_order_arent_derived_again() {
+        // 43 = p already says exactly one of 3 and 4 is present.
+        let statements = explain(&[guess("345", "p"), guess("234", "pp"), guess("43", "p")]);
+        assert!(statements
+            .iter()
+            .all(|statement| !statement.starts_with("Exactly one of 3 and 4")));
+        assert_eq!(statements[0], "5 is eliminated because 345 = p and 43 = p");
+    }
+
+    #[test]
+    fn repeated_symbols_say_nothing_about_where_they_go() {
+        let statements = explain(&[guess("11", "f")]);
+        assert!(statements
+            .iter()
+            .all(|statement| !statement.starts_with("Whichever")));
+    }
+}
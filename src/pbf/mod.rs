@@ -1,9 +1,14 @@
+pub mod analysis;
+pub mod candidates;
+pub mod explain;
+pub mod session;
 pub mod solver;
 
 use std::fmt;
 use std::fmt::Display;
+use std::str::FromStr;
 
-#[derive(Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct PbfStats {
     // Number of guess characters that exist in secret but not in the right position.
     pub p: i32,
@@ -12,6 +17,151 @@ pub struct PbfStats {
     pub f: i32,
 }
 
+// How guess symbols that appear more than once are counted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Scoring {
+    // Every guess symbol found anywhere in the secret counts, however many times the secret has it.
+    Set,
+    // Each symbol in the secret can only be matched once, like the pegs in Mastermind.
+    Multiset,
+}
+
+// What the guesser is told about each guess.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Feedback {
+    // Only how many symbols were in the right place and how many were elsewhere.
+    Counts,
+    // Which positions were right, which symbols were elsewhere in the secret, and which were absent.
+    Positions,
+}
+
+// The result for one position of a guess when using positional feedback.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PositionResult {
+    Exact,
+    Present,
+    Absent,
+}
+
+impl PbfStats {
+    pub fn from_positions(positions: &[PositionResult]) -> Self {
+        PbfStats {
+            f: positions
+                .iter()
+                .filter(|position| **position == PositionResult::Exact)
+                .count() as i32,
+            p: positions
+                .iter()
+                .filter(|position| **position == PositionResult::Present)
+                .count() as i32,
+        }
+    }
+
+    // Bulls and Cows notation, where bulls are fermis and cows are picos.
+    pub fn bulls_and_cows(&self) -> String {
+        format!("{}A{}B", self.f, self.p)
+    }
+
+    // Whether a guess of this length could get this outcome under the given scoring.
+    pub fn check_possible(&self, length: usize, scoring: Scoring) -> Result<(), String> {
+        let (f, p) = (self.f as usize, self.p as usize);
+        if f + p > length {
+            return Err(format!(
+                "{} counts more than the {} symbols in a guess",
+                self, length
+            ));
+        }
+        // With every other symbol in the right place, the last one can only be elsewhere if the secret has it twice, and each secret symbol only counts once.
+        if scoring == Scoring::Multiset && length > 0 && f == length - 1 && p == 1 {
+            return Err(format!(
+                "{} can't happen: if all but one symbol is in the right place, the last one can't be elsewhere",
+                self
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn parse_count(count: &str, outcome: &str) -> Result<i32, String> {
+    count
+        .trim()
+        .parse::<u16>()
+        .map(i32::from)
+        .map_err(|_ignored| invalid_outcome(outcome))
+}
+
+fn invalid_outcome(outcome: &str) -> String {
+    format!(
+        "{} is not an outcome. Write it like fp, 1f1p, 1A1B or 1,1 (fermis first), or b for nothing",
+        outcome
+    )
+}
+
+// Reads what Display writes ("b", or an f for each fermi and a p for each pico), and also:
+// counts before each letter like 1f2p, Bulls and Cows notation like 1A2B, and pairs of numbers like "1,2" or "1 2", fermis first.
+// Anything else is an error rather than being skipped. Whether the outcome is possible depends on the game, see check_possible.
+impl FromStr for PbfStats {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("Enter an outcome".to_string());
+        }
+        if s == "b" {
+            return Ok(PbfStats { p: 0, f: 0 });
+        }
+        if s.chars().all(|c| c == 'f' || c == 'p') {
+            return Ok(PbfStats {
+                p: s.chars().filter(|c| *c == 'p').count() as i32,
+                f: s.chars().filter(|c| *c == 'f').count() as i32,
+            });
+        }
+        if let Some((f, p)) = s
+            .split_once(',')
+            .or_else(|| s.split_once(char::is_whitespace))
+        {
+            return Ok(PbfStats {
+                p: parse_count(p, s)?,
+                f: parse_count(f, s)?,
+            });
+        }
+
+        // Counts before letters. The letters for fermis and picos are f and p, or A and B in Bulls and Cows notation, and can't be mixed.
+        let mut stats = PbfStats { p: 0, f: 0 };
+        let mut seen = Vec::new();
+        let mut count_start = 0;
+        for (i, c) in s.char_indices() {
+            if c.is_ascii_digit() {
+                continue;
+            }
+            let letter = c.to_ascii_lowercase();
+            let (field, bulls_and_cows) = match letter {
+                'f' => (&mut stats.f, false),
+                'p' => (&mut stats.p, false),
+                'a' => (&mut stats.f, true),
+                'b' => (&mut stats.p, true),
+                _ => return Err(invalid_outcome(s)),
+            };
+            if count_start == i
+                || seen.contains(&letter)
+                || seen
+                    .iter()
+                    .any(|seen| matches!(seen, 'a' | 'b') != bulls_and_cows)
+            {
+                return Err(invalid_outcome(s));
+            }
+            *field = parse_count(&s[count_start..i], s)?;
+            seen.push(letter);
+            count_start = i + 1;
+        }
+        if count_start != s.len() {
+            return Err(invalid_outcome(s));
+        }
+        Ok(stats)
+    }
+}
+
 impl Display for PbfStats {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.p == 0 && self.f == 0 {
@@ -26,3 +176,87 @@ impl Display for PbfStats {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(f: i32, p: i32) -> PbfStats {
+        PbfStats { p, f }
+    }
+
+    #[test]
+    fn displayed_outcomes_parse_back() {
+        for f in 0..=5 {
+            for p in 0..=5 {
+                let outcome = stats(f, p);
+                assert_eq!(outcome.to_string().parse(), Ok(outcome));
+            }
+        }
+    }
+
+    #[test]
+    fn every_notation_parses() {
+        assert_eq!("1f2p".parse(), Ok(stats(1, 2)));
+        assert_eq!("1A2B".parse(), Ok(stats(1, 2)));
+        assert_eq!("1,2".parse(), Ok(stats(1, 2)));
+        assert_eq!("1 2".parse(), Ok(stats(1, 2)));
+        assert_eq!("b".parse(), Ok(stats(0, 0)));
+    }
+
+    #[test]
+    fn malformed_outcomes_are_rejected() {
+        for outcome in ["x", "1fp", "1A2p"] {
+            assert!(outcome.parse::<PbfStats>().is_err(), "{}", outcome);
+        }
+    }
+
+    #[test]
+    fn impossible_outcomes_are_rejected() {
+        let outcome: PbfStats = "fffff".parse().unwrap();
+        assert!(outcome.check_possible(3, Scoring::Set).is_err());
+        let almost = stats(2, 1);
+        assert!(almost.check_possible(3, Scoring::Set).is_ok());
+        assert!(almost.check_possible(3, Scoring::Multiset).is_err());
+        assert!(stats(1, 2).check_possible(3, Scoring::Multiset).is_ok());
+    }
+
+    fn digits() -> Vec<char> {
+        "0123456789".chars().collect()
+    }
+
+    fn guesses_and_outcomes(guesses: &[solver::Guess<char>]) -> Vec<(String, PbfStats)> {
+        guesses
+            .iter()
+            .map(|guess| (guess.guess.iter().collect(), guess.result))
+            .collect()
+    }
+
+    #[test]
+    fn exported_sessions_parse_back() {
+        let mut guess_state = solver::GuessState::new(digits(), 3);
+        let guesses =
+            session::parse_session(&digits(), &guess_state, "123 fp\n456 b\n\n789 1 0\n").unwrap();
+        let expected = guesses_and_outcomes(&guesses);
+        for guess in guesses {
+            guess_state.add_guess(guess);
+        }
+        let exported = session::export_session(&guess_state);
+        let parsed = session::parse_session(&digits(), &guess_state, &exported).unwrap();
+        assert_eq!(guesses_and_outcomes(&parsed), expected);
+    }
+
+    #[test]
+    fn session_errors_name_their_lines() {
+        let guess_state = solver::GuessState::new(digits(), 3);
+        let errors =
+            match session::parse_session(&digits(), &guess_state, "123 fp\n\n12 f\n456 x\n789\n") {
+                Ok(_) => panic!("a session with bad lines parsed"),
+                Err(errors) => errors,
+            };
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0], "Line 3: Guesses must be 3 symbols long");
+        assert!(errors[1].starts_with("Line 4: "), "{}", errors[1]);
+        assert_eq!(errors[2], "Line 5: missing the outcome");
+    }
+}
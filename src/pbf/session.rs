@@ -0,0 +1,120 @@
+// Guesses and their outcomes written out one per line, like "123 fp", as the hint and verify pages and the verify command read them.
+use crate::pbf::solver::{can_enumerate, Guess, GuessState};
+use crate::pbf::{Feedback, PbfStats, Scoring};
+
+// What the hint and verify pages and the verify command offer unless told otherwise.
+pub const DEFAULT_SYMBOLS: &str = "0123456789";
+
+// The symbols secrets can use, without repeats or whitespace, in the order given.
+pub fn parse_alphabet(text: &str) -> Result<Vec<char>, String> {
+    let mut symbols: Vec<char> = Vec::new();
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        if !symbols.contains(&c) {
+            symbols.push(c);
+        }
+    }
+    if symbols.is_empty() {
+        return Err("Enter the symbols secrets can use".to_string());
+    }
+    Ok(symbols)
+}
+
+// A solver for checking a written out session, with the clues read from it.
+// Secrets are as long as the first guess.
+pub fn verifier_for(
+    text: &str,
+    symbols: &[char],
+    scoring: Scoring,
+) -> Result<(GuessState<char>, Vec<Guess<char>>), String> {
+    let length = text
+        .split_whitespace()
+        .next()
+        .map(|guess| guess.chars().count())
+        .ok_or("Enter the clues, one guess and its outcome per line")?;
+    if !can_enumerate(symbols.len(), length) {
+        return Err(format!(
+            "There are too many possible secrets with {} symbols and length {}",
+            symbols.len(),
+            length
+        ));
+    }
+    let solver = GuessState::with_rules(symbols.to_vec(), length, scoring, Feedback::Counts);
+    let clues = parse_session(symbols, &solver, text).map_err(|errors| errors.join("\n"))?;
+    Ok((solver, clues))
+}
+
+// Reads a guess like "123" and its outcome like "fp", checking both make sense for the guesses so far.
+pub fn parse_guess(
+    alphabet: &[char],
+    guess_state: &GuessState<char>,
+    symbols: &str,
+    results_string: &str,
+) -> Result<Guess<char>, String> {
+    let symbols: Vec<char> = symbols.chars().collect();
+    if let Some(c) = symbols.iter().find(|c| !alphabet.contains(c)) {
+        return Err(format!(
+            "{} is not one of {}",
+            c,
+            alphabet.iter().collect::<String>()
+        ));
+    }
+    if symbols.len() != guess_state.guess_length() {
+        return Err(format!(
+            "Guesses must be {} symbols long",
+            guess_state.guess_length()
+        ));
+    }
+    let result: PbfStats = results_string.parse()?;
+    result.check_possible(guess_state.guess_length(), guess_state.scoring())?;
+    Ok(Guess {
+        guess: symbols,
+        result,
+        positions: None,
+    })
+}
+
+// Reads a whole session, one guess and its outcome per line, like "123 fp". Blank lines are skipped.
+// Every line that can't be read gets its own error.
+pub fn parse_session(
+    alphabet: &[char],
+    guess_state: &GuessState<char>,
+    text: &str,
+) -> Result<Vec<Guess<char>>, Vec<String>> {
+    let mut guesses = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        // Everything after the guess is the outcome, which can itself have a space, like "1 2".
+        let parsed = match line.split_once(char::is_whitespace) {
+            Some((symbols, results)) => parse_guess(alphabet, guess_state, symbols, results),
+            None => Err("missing the outcome".to_string()),
+        };
+        match parsed {
+            Ok(guess) => guesses.push(guess),
+            Err(e) => errors.push(format!("Line {}: {}", i + 1, e)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(guesses)
+    } else {
+        Err(errors)
+    }
+}
+
+// The guesses so far in the format parse_session reads.
+pub fn export_session(guess_state: &GuessState<char>) -> String {
+    guess_state
+        .guesses()
+        .iter()
+        .map(|guess| {
+            format!(
+                "{} {}\n",
+                guess.guess.iter().collect::<String>(),
+                guess.result
+            )
+        })
+        .collect()
+}
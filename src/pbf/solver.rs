@@ -1,39 +1,129 @@
+use crate::pbf::candidates::{CandidateSet, Code, Codec, Symbols, MAX_LENGTH, UNKNOWN_SYMBOL};
+use crate::pbf::{Feedback, PositionResult, Scoring};
 use crate::secret::Secret;
 use crate::PbfStats;
-use itertools::Itertools;
-use std::collections::HashSet;
+use once_cell::unsync::OnceCell;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::hash::Hash;
 
-fn real_combinations<T, I>(items: I, times: usize) -> impl Iterator<Item = Vec<T>>
-where
-    I: Iterator<Item = T> + Clone,
-    T: Clone,
-{
-    std::iter::repeat(items)
-        .take(times)
-        .multi_cartesian_product()
+// Most candidates a solver will hold, which keeps the bitset of candidates to 2MB. Seven positions of ten digits fit.
+pub const MAX_CANDIDATES: usize = 1 << 24;
+
+// Scoring every guess against every candidate takes time proportional to the square of the number of candidates. Past these limits next_guess only looks at an evenly spread sample of each, so it stays quick but is no longer guaranteed to pick the best guess.
+pub const MAX_SCORED_GUESSES: usize = 1_000;
+pub const MAX_SCORED_CANDIDATES: usize = 1_000;
+
+// Whether guesses of that many symbols and that length fit in a packed code at all.
+pub fn can_pack(symbols: usize, length: usize) -> bool {
+    length <= MAX_LENGTH && symbols < UNKNOWN_SYMBOL as usize
+}
+
+// Whether a solver can hold every combination of that many symbols.
+pub fn can_enumerate(symbols: usize, length: usize) -> bool {
+    can_pack(symbols, length)
+        && matches!(symbols.checked_pow(length as u32), Some(size) if size <= MAX_CANDIDATES)
+}
+
+// What the remaining candidates say about one symbol in one position.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Knowledge {
+    // No candidate has the symbol there.
+    Impossible,
+    Possible,
+    // Every candidate has the symbol there.
+    Certain,
+}
+
+// Everything known about where each symbol can go, like the notes players keep on paper.
+pub struct KnowledgeGrid<T> {
+    pub symbols: Vec<T>,
+    // One row per symbol, in the same order as symbols, with one cell per position.
+    pub cells: Vec<Vec<Knowledge>>,
+    // For each symbol, the share of the remaining candidates that have it somewhere.
+    pub presence: Vec<f64>,
+}
+
+impl<T: Clone> KnowledgeGrid<T> {
+    // Symbols no remaining candidate uses, so the secret can't have them anywhere. Empty once there are no candidates, when every symbol would be.
+    pub fn absent_symbols(&self) -> Vec<T> {
+        let absent: Vec<T> = self
+            .symbols
+            .iter()
+            .zip(&self.cells)
+            .filter(|(_, row)| row.iter().all(|cell| *cell == Knowledge::Impossible))
+            .map(|(symbol, _)| symbol.clone())
+            .collect();
+        if absent.len() == self.symbols.len() {
+            return Vec::new();
+        }
+        absent
+    }
+
+    // The positions where every remaining candidate has the same symbol, with that symbol. Empty once there are no candidates.
+    pub fn known_positions(&self) -> Vec<(usize, T)> {
+        let mut known: Vec<(usize, T)> = self
+            .symbols
+            .iter()
+            .zip(&self.cells)
+            .flat_map(|(symbol, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, cell)| **cell == Knowledge::Certain)
+                    .map(move |(position, _)| (position, symbol.clone()))
+            })
+            .collect();
+        known.sort_by_key(|(position, _)| *position);
+        known
+    }
+}
+
+// Clues are tracked as bits of a u64 while verifying.
+pub const MAX_VERIFIED_CLUES: usize = 64;
+
+// Verifying lists at most this many of the solutions, however many there are.
+pub const MAX_LISTED_SOLUTIONS: usize = 1000;
+
+// What a set of clues leaves possible, for checking a puzzle has exactly one answer.
+pub struct Verification<T> {
+    // How many candidates fit every clue.
+    pub solution_count: usize,
+    // Up to MAX_LISTED_SOLUTIONS of them.
+    pub solutions: Vec<Vec<T>>,
+    // Indices of clues that leave the same solutions between them, none of which could be left out on its own. The rest are redundant given these.
+    // They're found by dropping clues one at a time, so there may be a smaller set of clues that would do.
+    pub necessary: Vec<usize>,
 }
 
 // A guess someone has made with the results.
 pub struct Guess<T> {
     pub guess: Vec<T>,
     pub result: PbfStats,
+    // Only known when playing with positional feedback.
+    pub positions: Option<Vec<PositionResult>>,
 }
 
 pub struct GuessState<T> {
-    cached_potential_outcomes: Vec<PbfStats>,
-
     guesses: Vec<Guess<T>>,
 
     // Based on the guesses, which combinations are still valid, A combination is valid if--were it to be the real secret number--the result of applying each guess in the set of guesses to the combination would produce the result associated with the guess.
-    available_guesses: Vec<Secret<T>>,
+    available_guesses: CandidateSet,
 
     // The things you can guess. For Pico, Bagel, Fermi, this is the digits from 0 to 9, inclusive.
     guess_space: Vec<T>,
 
-    // Length of the solution.
-    guess_length: usize,
+    // Where each symbol is in the guess space, which is how it's written in a packed code.
+    symbol_indices: HashMap<T, u8>,
+
+    // Packs guesses of the solution's length into codes.
+    codec: Codec,
+
+    scoring: Scoring,
+
+    feedback: Feedback,
+
+    // The candidates guesses are scored against, kept until the next guess changes them.
+    candidate_sample: OnceCell<Vec<Symbols>>,
 }
 
 impl<T> GuessState<T>
@@ -41,68 +131,637 @@ where
     T: Clone + Eq + Hash + Debug,
 {
     pub fn new(guess_space: Vec<T>, guess_length: usize) -> GuessState<T> {
-        #[derive(Clone, Eq, PartialEq)]
-        enum Pbf {
-            P,
-            B,
-            F,
-        }
-        let cached_potential_outcomes =
-            real_combinations(vec![Pbf::P, Pbf::B, Pbf::F].into_iter(), guess_length)
-                .map(|result| PbfStats {
-                    p: result.iter().filter(|pbf| pbf == &&Pbf::P).count() as i32,
-                    f: result.iter().filter(|pbf| pbf == &&Pbf::F).count() as i32,
-                })
-                .collect();
+        Self::with_rules(guess_space, guess_length, Scoring::Set, Feedback::Counts)
+    }
+
+    // Every combination of the guess space is a candidate. Panics unless can_enumerate allows it.
+    pub fn with_rules(
+        guess_space: Vec<T>,
+        guess_length: usize,
+        scoring: Scoring,
+        feedback: Feedback,
+    ) -> GuessState<T> {
+        assert!(
+            can_enumerate(guess_space.len(), guess_length),
+            "Too many combinations to solve"
+        );
+        let codec = Codec::new(guess_space.len(), guess_length);
+        let size = codec.size().unwrap_or(0);
+        Self::with_candidates(
+            guess_space,
+            codec,
+            CandidateSet::all(size),
+            scoring,
+            feedback,
+        )
+    }
+
+    // Holds no candidates, so it's only good for checking guesses against the results so far with contradiction. Needs no more than can_pack allows.
+    pub fn without_candidates(
+        guess_space: Vec<T>,
+        guess_length: usize,
+        scoring: Scoring,
+        feedback: Feedback,
+    ) -> GuessState<T> {
+        let codec = Codec::new(guess_space.len(), guess_length);
+        Self::with_candidates(
+            guess_space,
+            codec,
+            CandidateSet::from_list(Vec::new()),
+            scoring,
+            feedback,
+        )
+    }
+
+    // For games where the secret is one of a list of words, which is much smaller than every combination of letters.
+    pub fn from_dictionary(
+        dictionary: Vec<Vec<T>>,
+        scoring: Scoring,
+        feedback: Feedback,
+    ) -> GuessState<T> {
+        let mut guess_space: Vec<T> = Vec::new();
+        for symbol in dictionary.iter().flatten() {
+            if !guess_space.contains(symbol) {
+                guess_space.push(symbol.clone());
+            }
+        }
+        let guess_length = dictionary.first().map(|word| word.len()).unwrap_or(0);
+        let codec = Codec::new(guess_space.len(), guess_length);
+        let mut state = Self::with_candidates(
+            guess_space,
+            codec,
+            CandidateSet::from_list(Vec::new()),
+            scoring,
+            feedback,
+        );
+        state.available_guesses = CandidateSet::from_list(
+            dictionary
+                .iter()
+                .filter_map(|word| codec.encode(&state.symbols(word)?))
+                .collect(),
+        );
+        state
+    }
+
+    fn with_candidates(
+        guess_space: Vec<T>,
+        codec: Codec,
+        available_guesses: CandidateSet,
+        scoring: Scoring,
+        feedback: Feedback,
+    ) -> GuessState<T> {
+        let symbol_indices = guess_space
+            .iter()
+            .enumerate()
+            .map(|(index, symbol)| (symbol.clone(), index as u8))
+            .collect();
         Self {
-            cached_potential_outcomes,
             guesses: Vec::new(),
-            available_guesses: real_combinations(guess_space.clone().into_iter(), guess_length)
-                .map(Secret::new)
-                .collect(),
-            guess_space: guess_space,
-            guess_length,
+            available_guesses,
+            guess_space,
+            symbol_indices,
+            codec,
+            scoring,
+            feedback,
+            candidate_sample: OnceCell::new(),
+        }
+    }
+
+    // The guess with each symbol replaced by its index. Symbols outside the guess space can't match anything. None if the guess isn't the solution's length.
+    fn symbols(&self, guess: &[T]) -> Option<Symbols> {
+        if guess.len() != self.codec.length() {
+            return None;
+        }
+        let mut symbols = [UNKNOWN_SYMBOL; MAX_LENGTH];
+        for (index, symbol) in symbols.iter_mut().zip(guess) {
+            *index = self
+                .symbol_indices
+                .get(symbol)
+                .cloned()
+                .unwrap_or(UNKNOWN_SYMBOL);
         }
+        Some(symbols)
+    }
+
+    fn unpack(&self, code: Code) -> Vec<T> {
+        self.codec.decode(code)[..self.codec.length()]
+            .iter()
+            .map(|index| self.guess_space[*index as usize].clone())
+            .collect()
     }
 
     pub fn guesses(&self) -> &Vec<Guess<T>> {
         &self.guesses
     }
 
+    pub fn guess_length(&self) -> usize {
+        self.codec.length()
+    }
+
+    pub fn scoring(&self) -> Scoring {
+        self.scoring
+    }
+
+    // How many values the secret could still have.
+    pub fn candidate_count(&self) -> usize {
+        self.available_guesses.len()
+    }
+
+    // Everything that could still be the secret.
+    pub fn candidates(&self) -> impl Iterator<Item = Vec<T>> + '_ {
+        self.available_guesses
+            .iter()
+            .map(move |code| self.unpack(code))
+    }
+
+    // Goes through every remaining candidate, so it's as slow as scoring a guess against all of them.
+    pub fn knowledge_grid(&self) -> KnowledgeGrid<T> {
+        let length = self.codec.length();
+        let symbols = self.guess_space.len();
+        let mut at_position = vec![vec![0usize; length]; symbols];
+        let mut anywhere = vec![0usize; symbols];
+        let mut total = 0;
+        for code in self.available_guesses.iter() {
+            let candidate = self.codec.decode(code);
+            for (position, index) in candidate[..length].iter().enumerate() {
+                at_position[*index as usize][position] += 1;
+                if !candidate[..position].contains(index) {
+                    anywhere[*index as usize] += 1;
+                }
+            }
+            total += 1;
+        }
+        KnowledgeGrid {
+            symbols: self.guess_space.clone(),
+            cells: at_position
+                .iter()
+                .map(|counts| {
+                    counts
+                        .iter()
+                        .map(|count| match *count {
+                            0 => Knowledge::Impossible,
+                            count if count == total => Knowledge::Certain,
+                            _ => Knowledge::Possible,
+                        })
+                        .collect()
+                })
+                .collect(),
+            presence: anywhere
+                .iter()
+                .map(|count| {
+                    if total == 0 {
+                        0.0
+                    } else {
+                        *count as f64 / total as f64
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    // Whether one of the guesses was the secret.
+    pub fn is_solved(&self) -> bool {
+        self.guesses
+            .iter()
+            .any(|guess| guess.result.f == self.codec.length() as i32)
+    }
+
+    // Scores a guess against a known secret, the way this state's rules would.
+    pub fn score(&self, secret: &Secret<T>, guess: Vec<T>) -> Guess<T> {
+        let (result, positions) = match self.feedback {
+            Feedback::Counts => (secret.compare_with(&guess, self.scoring), None),
+            Feedback::Positions => {
+                let positions = secret.compare_positions(&guess);
+                (PbfStats::from_positions(&positions), Some(positions))
+            }
+        };
+        Guess {
+            guess,
+            result,
+            positions,
+        }
+    }
+
+    // Scores a guess like a codemaker who never settled on a secret: of the outcomes the remaining candidates would give, the one that keeps the most of them. The guess only wins once nothing else is left.
+    // Unlike score_guess this partitions every candidate rather than a sample, since leaving one out would let the guesser win early. None if no candidates are left or the guess is the wrong length.
+    pub fn evil_score(&self, guess: Vec<T>) -> Option<Guess<T>> {
+        let guess_symbols = self.symbols(&guess)?;
+        let win = self.codec.length() as u64 * (MAX_LENGTH as u64 + 1);
+        // How many candidates give each outcome, with one of them to score the guess against.
+        let mut partitions: HashMap<u64, (usize, Code)> = HashMap::new();
+        for code in self.available_guesses.iter() {
+            let partition = partitions
+                .entry(self.outcome(&self.codec.decode(code), &guess_symbols))
+                .or_insert((0, code));
+            partition.0 += 1;
+        }
+        let (_, (_, code)) = partitions.into_iter().max_by_key(|(outcome, (count, _))| {
+            let wins = match self.feedback {
+                Feedback::Counts => *outcome == win,
+                Feedback::Positions => *outcome == 0,
+            };
+            // Ties go to the outcome that doesn't win, then to the lowest, so the same guesses always get the same answers.
+            (*count, !wins, std::cmp::Reverse(*outcome))
+        })?;
+        Some(self.score(&Secret::new(self.unpack(code)), guess))
+    }
+
+    // The position results of a guess against a candidate, as a number that's equal for equal results.
+    fn position_outcome(&self, candidate: &Symbols, guess: &Symbols) -> u64 {
+        self.codec
+            .compare_positions(candidate, guess)
+            .iter()
+            .fold(0, |outcome, position| {
+                outcome * 3
+                    + match position {
+                        PositionResult::Exact => 0,
+                        PositionResult::Present => 1,
+                        PositionResult::Absent => 2,
+                    }
+            })
+    }
+
+    // The outcome of a guess against a candidate under this state's feedback, as a number that's equal for equal outcomes. Counts stay below (MAX_LENGTH + 1) squared.
+    fn outcome(&self, candidate: &Symbols, guess: &Symbols) -> u64 {
+        match self.feedback {
+            Feedback::Counts => {
+                let result = self.codec.compare(candidate, guess, self.scoring);
+                result.f as u64 * (MAX_LENGTH as u64 + 1) + result.p as u64
+            }
+            Feedback::Positions => self.position_outcome(candidate, guess),
+        }
+    }
+
+    // Whether the guess would have got its results if the candidate were the secret.
+    fn consistent_filter(&self, guess: &Guess<T>) -> impl Fn(&Symbols) -> bool + '_ {
+        // A guess of the wrong length couldn't have got any results, so it rules out everything.
+        let guess_symbols = self.symbols(&guess.guess);
+        let expected = match &guess.positions {
+            Some(positions) => Err(positions.clone()),
+            None => Ok(guess.result),
+        };
+        move |candidate| match (&guess_symbols, &expected) {
+            (None, _) => false,
+            (Some(guess_symbols), Ok(result)) => {
+                self.codec.compare(candidate, guess_symbols, self.scoring) == *result
+            }
+            (Some(guess_symbols), Err(positions)) => {
+                &self.codec.compare_positions(candidate, guess_symbols) == positions
+            }
+        }
+    }
+
+    // How many of the remaining candidates would still be left after this guess.
+    pub fn count_consistent(&self, guess: &Guess<T>) -> usize {
+        let consistent = self.consistent_filter(guess);
+        self.available_guesses
+            .iter()
+            .filter(|code| consistent(&self.codec.decode(*code)))
+            .count()
+    }
+
+    // Checks clues against the candidates this state has, without adding them as guesses.
+    pub fn verify(&self, clues: &[Guess<T>]) -> Result<Verification<T>, String> {
+        if clues.len() > MAX_VERIFIED_CLUES {
+            return Err(format!("There can be at most {} clues", MAX_VERIFIED_CLUES));
+        }
+        let filters: Vec<_> = clues
+            .iter()
+            .map(|clue| self.consistent_filter(clue))
+            .collect();
+        // Candidates grouped by which clues rule them out, one bit per clue. There are usually far fewer groups than candidates, and they're all it takes to count the solutions to any subset of the clues.
+        let mut ruled_out_by: HashMap<u64, usize> = HashMap::new();
+        let mut solutions = Vec::new();
+        for code in self.available_guesses.iter() {
+            let candidate = self.codec.decode(code);
+            let mask = filters
+                .iter()
+                .enumerate()
+                .filter(|(_, consistent)| !consistent(&candidate))
+                .fold(0u64, |mask, (i, _)| mask | 1 << i);
+            if mask == 0 && solutions.len() < MAX_LISTED_SOLUTIONS {
+                solutions.push(self.unpack(code));
+            }
+            *ruled_out_by.entry(mask).or_insert(0) += 1;
+        }
+        let solution_count = |clues: u64| -> usize {
+            ruled_out_by
+                .iter()
+                .filter(|(mask, _)| *mask & clues == 0)
+                .map(|(_, count)| count)
+                .sum()
+        };
+        let all = (0..clues.len()).fold(0u64, |mask, i| mask | 1 << i);
+        let count = solution_count(all);
+        // Leaving out a clue that doesn't let any more candidates in loses nothing. Later clues go first, so the earlier ones are kept when there's a choice.
+        let mut necessary = all;
+        for i in (0..clues.len()).rev() {
+            if solution_count(necessary & !(1 << i)) == count {
+                necessary &= !(1 << i);
+            }
+        }
+        Ok(Verification {
+            solution_count: count,
+            solutions,
+            necessary: (0..clues.len())
+                .filter(|i| necessary & 1 << i != 0)
+                .collect(),
+        })
+    }
+
+    // The first guess so far whose results rule out the candidate as the secret, by the same check add_guess uses to drop candidates.
+    // Symbols outside the guess space never match, so a candidate using them is only judged fairly if the guess space includes them. A candidate of the wrong length is ruled out by the first guess.
+    pub fn contradiction(&self, candidate: &[T]) -> Option<&Guess<T>> {
+        let candidate = match self.symbols(candidate) {
+            Some(candidate) => candidate,
+            None => return self.guesses.first(),
+        };
+        self.guesses
+            .iter()
+            .find(|guess| !self.consistent_filter(guess)(&candidate))
+    }
+
     pub fn add_guess(&mut self, guess: Guess<T>) {
+        let mut available_guesses =
+            std::mem::replace(&mut self.available_guesses, CandidateSet::all(0));
+        {
+            let consistent = self.consistent_filter(&guess);
+            available_guesses.retain(|code| consistent(&self.codec.decode(code)));
+        }
+        self.available_guesses = available_guesses;
+        self.candidate_sample = OnceCell::new();
         self.guesses.push(guess);
+    }
 
-        // Rust doesn't like when we use self for some reason so work around it by borrowing here.
-        let self_guesses = &self.guesses;
-        self.available_guesses.retain(|possible_solution| {
-            self_guesses
+    // The only candidate left, if it's down to one.
+    fn answer(&self) -> Option<Vec<T>> {
+        match self.available_guesses.len() {
+            1 => self
+                .available_guesses
                 .iter()
-                .all(|guess| possible_solution.compare(&guess.guess) == guess.result)
-        });
+                .next()
+                .map(|code| self.unpack(code)),
+            _ => None,
+        }
+    }
+
+    fn candidate_sample(&self) -> &[Symbols] {
+        self.candidate_sample.get_or_init(|| {
+            self.available_guesses
+                .sample(MAX_SCORED_CANDIDATES)
+                .into_iter()
+                .map(|code| self.codec.decode(code))
+                .collect()
+        })
+    }
+
+    // What may be worth guessing: a sample of everything that may be guessed, plus a sample of the candidates, since those might also win outright.
+    fn guess_pool(&self) -> impl Iterator<Item = Code> + '_ {
+        let guessed: HashSet<Code> = self
+            .guesses
+            .iter()
+            .filter_map(|guess| self.codec.encode(&self.symbols(&guess.guess)?))
+            .collect();
+        self.available_guesses
+            .sample_all(MAX_SCORED_GUESSES)
+            .chain(self.available_guesses.sample(MAX_SCORED_GUESSES))
+            .filter(move |code| !guessed.contains(code))
     }
 
     pub fn next_guess(&self) -> Option<Vec<T>> {
-        if let [answer] = &*self.available_guesses {
-            Some(answer.as_guess().to_vec())
-        } else {
-            // Index the guesses so we can quickly check whether we've already guessed it.
-            let indexed_guesses: HashSet<&Vec<T>> =
-                self.guesses.iter().map(|guess| &guess.guess).collect();
-            real_combinations(self.guess_space.clone().into_iter(), self.guess_length)
-                .filter(|guess| !indexed_guesses.contains(guess))
-                .max_by_key(|guess| self.score_guess(guess))
+        if let Some(answer) = self.answer() {
+            return Some(answer);
         }
+        self.guess_pool()
+            .max_by_key(|code| {
+                (
+                    self.score_symbols(&self.codec.decode(*code)),
+                    self.available_guesses.contains(*code),
+                )
+            })
+            .map(|code| self.unpack(code))
+    }
+
+    // The number of candidates this guess is guaranteed to eliminate, whatever the outcome, out of a sample of at most MAX_SCORED_CANDIDATES of them. A guess of the wrong length can't be scored, so it eliminates none.
+    pub fn score_guess(&self, guess: &[T]) -> usize {
+        self.symbols(guess)
+            .map(|guess| self.score_symbols(&guess))
+            .unwrap_or(0)
+    }
+
+    fn score_symbols(&self, guess: &Symbols) -> usize {
+        let sample = self.candidate_sample();
+        // Partition the candidates by the outcome they would produce. The worst outcome is the one that keeps the biggest partition.
+        let largest_partition = match self.feedback {
+            // There are few enough possible counts to keep the partitions in an array.
+            Feedback::Counts => {
+                let mut partitions = [0usize; (MAX_LENGTH + 1) * (MAX_LENGTH + 1)];
+                for candidate in sample {
+                    partitions[self.outcome(candidate, guess) as usize] += 1;
+                }
+                partitions.iter().max().cloned().unwrap_or(0)
+            }
+            Feedback::Positions => {
+                let mut partitions: HashMap<u64, usize> = HashMap::new();
+                for candidate in sample {
+                    *partitions
+                        .entry(self.outcome(candidate, guess))
+                        .or_insert(0) += 1;
+                }
+                partitions.values().max().cloned().unwrap_or(0)
+            }
+        };
+        sample.len() - largest_partition
+    }
+}
+
+// Several independent secrets guessed at the same time, where every guess is scored against each of them.
+pub struct MultiGuessState<T> {
+    // One per secret, each holding the candidates for that secret.
+    states: Vec<GuessState<T>>,
+}
+
+impl<T> MultiGuessState<T>
+where
+    T: Clone + Eq + Hash + Debug,
+{
+    // One state per secret, each starting from the same candidates.
+    pub fn new(states: Vec<GuessState<T>>) -> Self {
+        Self { states }
     }
 
-    fn score_guess(&self, guess: &Vec<T>) -> Option<usize> {
-        self.cached_potential_outcomes
+    pub fn states(&self) -> &Vec<GuessState<T>> {
+        &self.states
+    }
+
+    // One guess per secret, in the same order as the secrets, each holding that secret's result.
+    pub fn add_guess(&mut self, guesses: Vec<Guess<T>>) {
+        for (state, guess) in self.states.iter_mut().zip(guesses) {
+            state.add_guess(guess);
+        }
+    }
+
+    pub fn next_guess(&self) -> Option<Vec<T>> {
+        let unsolved: Vec<&GuessState<T>> = self
+            .states
             .iter()
-            .map(|outcome| {
-                self.available_guesses
+            .filter(|state| !state.is_solved())
+            .collect();
+        // A secret we've pinned down is worth guessing straight away, since nothing else can do better for it.
+        if let Some(answer) = unsolved.iter().find_map(|state| state.answer()) {
+            return Some(answer);
+        }
+        // The states share a guess space, so a code means the same guess in each of them.
+        let first = unsolved.first()?;
+        first
+            .guess_pool()
+            .max_by_key(|code| {
+                let guess = first.codec.decode(*code);
+                let score: usize = unsolved
+                    .iter()
+                    .map(|state| state.score_symbols(&guess))
+                    .sum();
+                let is_candidate = unsolved
                     .iter()
-                    .filter(|available_guess| &available_guess.compare(guess) != outcome)
-                    .count()
+                    .any(|state| state.available_guesses.contains(*code));
+                (score, is_candidate)
             })
-            .min()
+            .map(|code| first.unpack(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    fn clue(guess: &str, f: i32, p: i32) -> Guess<char> {
+        Guess {
+            guess: digits(guess),
+            result: PbfStats { f, p },
+            positions: None,
+        }
+    }
+
+    fn solver() -> GuessState<char> {
+        GuessState::new(('0'..='9').collect(), 3)
+    }
+
+    #[test]
+    fn wrong_length_candidates_are_contradicted() {
+        let mut state = solver();
+        state.add_guess(clue("123", 0, 0));
+        assert!(state.contradiction(&digits("456")).is_none());
+        for candidate in ["45", "4567"] {
+            let contradiction = state.contradiction(&digits(candidate));
+            assert_eq!(
+                contradiction.map(|guess| &guess.guess),
+                Some(&digits("123"))
+            );
+        }
+    }
+
+    #[test]
+    fn evil_score_keeps_the_largest_partition() {
+        let state = solver();
+        let mut partitions: HashMap<(i32, i32), usize> = HashMap::new();
+        for candidate in 0..1000 {
+            let candidate = digits(&format!("{:03}", candidate));
+            let result = state.score(&Secret::new(candidate), digits("123")).result;
+            *partitions.entry((result.f, result.p)).or_insert(0) += 1;
+        }
+        let largest = partitions.values().max().cloned().unwrap();
+        let scored = state.evil_score(digits("123")).unwrap();
+        assert_eq!(partitions[&(scored.result.f, scored.result.p)], largest);
+        assert_eq!(state.count_consistent(&scored), largest);
+    }
+
+    #[test]
+    fn evil_score_only_wins_once_one_candidate_is_left() {
+        let mut state = GuessState::from_dictionary(
+            vec![digits("123"), digits("456")],
+            Scoring::Set,
+            Feedback::Counts,
+        );
+        // Winning and missing both keep one candidate, and the tie goes to the miss.
+        let scored = state.evil_score(digits("123")).unwrap();
+        assert_eq!(scored.result, PbfStats { f: 0, p: 0 });
+        state.add_guess(scored);
+        assert_eq!(state.candidate_count(), 1);
+        let scored = state.evil_score(digits("456")).unwrap();
+        assert_eq!(scored.result, PbfStats { f: 3, p: 0 });
+    }
+
+    #[test]
+    fn wrong_length_guesses_have_no_evil_score() {
+        let state = solver();
+        for guess in ["12", "1234"] {
+            assert!(state.evil_score(digits(guess)).is_none());
+        }
+    }
+
+    #[test]
+    fn wrong_length_guesses_fit_no_candidate() {
+        let mut state = solver();
+        for guess in ["12", "1234"] {
+            assert_eq!(state.score_guess(&digits(guess)), 0);
+        }
+        state.add_guess(clue("123", 0, 0));
+        assert_eq!(state.candidate_count(), 343);
+        state.add_guess(clue("4567", 0, 0));
+        assert_eq!(state.candidate_count(), 0);
+    }
+
+    #[test]
+    fn wrong_length_clues_fit_no_candidate() {
+        let state = solver();
+        for guess in ["12", "1234"] {
+            assert_eq!(state.count_consistent(&clue(guess, 0, 0)), 0);
+            let verification = state.verify(&[clue(guess, 0, 0)]).unwrap();
+            assert_eq!(verification.solution_count, 0);
+            assert!(verification.solutions.is_empty());
+        }
+    }
+
+    #[test]
+    fn classic_lock_puzzle_has_one_answer() {
+        let clues = vec![
+            clue("682", 1, 0),
+            clue("614", 0, 1),
+            clue("206", 0, 2),
+            clue("738", 0, 0),
+            clue("780", 0, 1),
+        ];
+        let verification = solver().verify(&clues).unwrap();
+        assert_eq!(verification.solution_count, 1);
+        assert_eq!(verification.solutions, vec![digits("042")]);
+        assert_eq!(verification.necessary, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn grid_shows_absent_symbols_and_known_positions() {
+        let mut state = solver();
+        state.add_guess(clue("789", 0, 0));
+        state.add_guess(clue("014", 1, 0));
+        let grid = state.knowledge_grid();
+        assert_eq!(grid.absent_symbols(), digits("789"));
+        assert!(grid.known_positions().is_empty());
+        state.add_guess(clue("023", 2, 0));
+        state.add_guess(clue("056", 1, 0));
+        let grid = state.knowledge_grid();
+        assert_eq!(grid.known_positions(), vec![(0, '0')]);
+        state.add_guess(clue("123", 3, 0));
+        assert_eq!(state.knowledge_grid().absent_symbols(), Vec::<char>::new());
+    }
+
+    #[test]
+    fn contradicting_clues_have_no_answer() {
+        let clues = vec![clue("123", 0, 0), clue("321", 1, 0)];
+        let verification = solver().verify(&clues).unwrap();
+        assert_eq!(verification.solution_count, 0);
+        assert!(verification.solutions.is_empty());
     }
 }
@@ -0,0 +1,391 @@
+use crate::daily::next_random;
+use crate::game::GuessState;
+use crate::pbf::explain::explain;
+use crate::pbf::solver::{Guess, GuessState as SolverState};
+use crate::pbf::{Feedback, Scoring};
+use crate::render_guesses;
+use crate::rules::{Rules, SecretPayload};
+use crate::secret::Secret;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::{html, Component, ComponentLink, Html, NodeRef};
+
+// A puzzle that doesn't fit the difficulty is thrown away and another tried, up to this many times before giving up on the seed.
+const MAX_ATTEMPTS: usize = 200;
+
+// Random clues stop being added once this many haven't narrowed things down, and the attempt starts over.
+const MAX_WASTED_CLUES: usize = 100;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<Self, ()> {
+        match name {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(()),
+        }
+    }
+
+    fn length(self) -> usize {
+        match self {
+            Difficulty::Easy | Difficulty::Medium => 3,
+            Difficulty::Hard => 4,
+        }
+    }
+
+    // Whether a puzzle needing that many clues and steps of reasoning belongs at this level.
+    fn fits(self, clues: usize, steps: usize) -> bool {
+        match self {
+            Difficulty::Easy => clues <= 4 && steps <= 4,
+            Difficulty::Medium => clues >= 4 && (5..=7).contains(&steps),
+            Difficulty::Hard => clues >= 4 && steps >= 7,
+        }
+    }
+}
+
+// The classic lock puzzle: a few guesses at a code of different digits with their results, from which exactly one code follows.
+pub struct Puzzle {
+    pub secret: String,
+    pub clues: Vec<Guess<char>>,
+    // How many statements it takes the explainer to write out what the clues say.
+    pub steps: usize,
+}
+
+impl Puzzle {
+    // The clues as a game against the secret, which is how they get shown.
+    fn game(&self) -> GuessState {
+        let mut game = GuessState::new(SecretPayload {
+            secrets: vec![self.secret.clone()],
+            rules: Rules::default(),
+        });
+        for clue in &self.clues {
+            game.guess(clue.guess.iter().collect());
+        }
+        game
+    }
+}
+
+// Every code of that many different digits, which are the codes a puzzle could have.
+fn distinct_codes(length: usize) -> Vec<Vec<char>> {
+    let mut codes: Vec<Vec<char>> = vec![Vec::new()];
+    for _ in 0..length {
+        codes = codes
+            .iter()
+            .flat_map(|code| {
+                ('0'..='9')
+                    .filter(move |digit| !code.contains(digit))
+                    .map(move |digit| {
+                        let mut longer = code.clone();
+                        longer.push(digit);
+                        longer
+                    })
+            })
+            .collect();
+    }
+    codes
+}
+
+fn random_code(state: &mut u64, length: usize) -> Vec<char> {
+    let mut digits: Vec<char> = ('0'..='9').collect();
+    for i in (1..digits.len()).rev() {
+        let j = (next_random(state) % (i as u64 + 1)) as usize;
+        digits.swap(i, j);
+    }
+    digits.truncate(length);
+    digits
+}
+
+fn copy_clue(clue: &Guess<char>) -> Guess<char> {
+    Guess {
+        guess: clue.guess.clone(),
+        result: clue.result,
+        positions: None,
+    }
+}
+
+// A solver for codes of different digits.
+fn solver(length: usize) -> SolverState<char> {
+    SolverState::from_dictionary(distinct_codes(length), Scoring::Set, Feedback::Counts)
+}
+
+// Random clues for a random code, added until only that code fits them, then cut back to the ones that are needed.
+// None if random clues stopped narrowing things down before getting there.
+fn attempt(state: &mut u64, length: usize) -> Option<Puzzle> {
+    let secret = random_code(state, length);
+    let scorer = Secret::new(secret.clone());
+    let mut clues: Vec<Guess<char>> = Vec::new();
+    let mut remaining = solver(length);
+    let mut wasted = 0;
+    while remaining.candidate_count() > 1 {
+        let guess = random_code(state, length);
+        let clue = Guess {
+            result: scorer.compare(&guess),
+            guess,
+            positions: None,
+        };
+        // A clue that gives the code away isn't much of a puzzle.
+        if clue.result.f as usize == length
+            || remaining.count_consistent(&clue) == remaining.candidate_count()
+        {
+            wasted += 1;
+            if wasted > MAX_WASTED_CLUES {
+                return None;
+            }
+            continue;
+        }
+        remaining.add_guess(copy_clue(&clue));
+        clues.push(clue);
+    }
+    let clues: Vec<Guess<char>> = solver(length)
+        .verify(&clues)
+        .ok()?
+        .necessary
+        .iter()
+        .map(|i| copy_clue(&clues[*i]))
+        .collect();
+    Some(Puzzle {
+        secret: secret.into_iter().collect(),
+        steps: explain(&clues).len(),
+        clues,
+    })
+}
+
+// The same seed and difficulty always give the same puzzle, so puzzles can be linked to.
+// None if no puzzle that fits the difficulty turned up.
+pub fn generate(difficulty: Difficulty, seed: u64) -> Option<Puzzle> {
+    let mut state = seed;
+    (0..MAX_ATTEMPTS)
+        .filter_map(|_| attempt(&mut state, difficulty.length()))
+        .find(|puzzle| difficulty.fits(puzzle.clues.len(), puzzle.steps))
+}
+
+fn with_game(puzzle: Option<Puzzle>) -> Option<(Puzzle, GuessState)> {
+    puzzle.map(|puzzle| {
+        let game = puzzle.game();
+        (puzzle, game)
+    })
+}
+
+fn random_seed() -> u64 {
+    (js_sys::Math::random() * u32::MAX as f64) as u64
+}
+
+// Reads the difficulty and seed from the query portion of the url, picking a new seed if there isn't one.
+fn linked_puzzle() -> (Difficulty, u64) {
+    let mut difficulty = Difficulty::Easy;
+    let mut seed = None;
+    let search = web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .unwrap_or_default();
+    for parameter in search.trim_start_matches('?').split('&') {
+        match parameter.split_once('=') {
+            Some(("difficulty", value)) => {
+                difficulty = Difficulty::from_name(value).unwrap_or(difficulty)
+            }
+            Some(("seed", value)) => seed = value.parse().ok(),
+            _ => {}
+        }
+    }
+    (difficulty, seed.unwrap_or_else(random_seed))
+}
+
+// A lock puzzle to solve by reasoning alone: the guesses are already made, and there's only the one answer to give.
+pub struct PuzzleComponent {
+    link: ComponentLink<Self>,
+    difficulty: Difficulty,
+    seed: u64,
+    // The puzzle with its clues as a game against the answer. None if the seed didn't give a puzzle of the difficulty.
+    puzzle: Option<(Puzzle, GuessState)>,
+    // Whether the last answer given was right, or why it can't be.
+    checked: Option<Result<(), String>>,
+    revealed: bool,
+    answer_ref: NodeRef,
+    difficulty_ref: NodeRef,
+}
+
+pub enum Msg {
+    Check,
+    Reveal,
+    NewPuzzle,
+}
+
+impl PuzzleComponent {
+    fn show_puzzle(&mut self, difficulty: Difficulty, seed: u64) {
+        self.difficulty = difficulty;
+        self.seed = seed;
+        self.puzzle = with_game(generate(difficulty, seed));
+        self.checked = None;
+        self.revealed = false;
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let (puzzle, game) = self.puzzle.as_ref().ok_or("There's no puzzle to solve")?;
+        let answer = self
+            .answer_ref
+            .cast::<HtmlInputElement>()
+            .unwrap()
+            .value()
+            .trim()
+            .to_string();
+        let digits: Vec<char> = answer.chars().collect();
+        if digits.len() != game.secret_length || !digits.iter().all(char::is_ascii_digit) {
+            return Err(format!("The code is {} digits", game.secret_length));
+        }
+        if (1..digits.len()).any(|i| digits[..i].contains(&digits[i])) {
+            return Err("The digits of the code are all different".to_string());
+        }
+        if answer == puzzle.secret {
+            return Ok(());
+        }
+        Err(game
+            .contradiction(&answer)
+            .unwrap_or_else(|| format!("{} isn't the code", answer)))
+    }
+
+    fn render_answer(&self, puzzle: &Puzzle) -> Html {
+        let checked_html = match &self.checked {
+            Some(Ok(())) => html! {<p class="status">{"That's the code. Well reasoned!"}</p>},
+            Some(Err(e)) => html! {<span class="error">{e}</span>},
+            None => html! {},
+        };
+        let revealed_html = if self.revealed {
+            html! {<p class="reveal">{format!("The code was {}", puzzle.secret)}</p>}
+        } else {
+            html! {<input type="submit" value="Show the answer" onclick=self.link.callback(|_| Msg::Reveal)/>}
+        };
+        html! {
+            <div>
+                <label for="puzzle_answer">{"Code"}</label>
+                <input type="text" id="puzzle_answer" ref=self.answer_ref.clone()/>
+                <input type="submit" value="Check" onclick=self.link.callback(|_| Msg::Check)/>
+                {checked_html}
+                {revealed_html}
+            </div>
+        }
+    }
+
+    fn render_puzzle(&self) -> Html {
+        let (puzzle, game) = match &self.puzzle {
+            Some(shown) => shown,
+            None => {
+                return html! {
+                    <p class="error">{"Couldn't make a puzzle of that difficulty from this seed. Try a new puzzle."}</p>
+                }
+            }
+        };
+        html! {
+            <div>
+                <p>{format!(
+                    "Someone has already made these guesses at a code of {} different digits. Only one code fits all of the results. What is it?",
+                    game.secret_length
+                )}</p>
+                <div class="guesses">
+                    {render_guesses(game)}
+                </div>
+                {self.render_answer(puzzle)}
+                <p><a href={format!("/puzzle.html?difficulty={}&seed={}", self.difficulty.name(), self.seed)}>{"Link to this puzzle"}</a></p>
+            </div>
+        }
+    }
+
+    fn render_new_puzzle(&self) -> Html {
+        let option = |difficulty: Difficulty, label: &str| {
+            html! {
+                <option value=difficulty.name() selected=self.difficulty == difficulty>{label}</option>
+            }
+        };
+        html! {
+            <div>
+                <label for="puzzle_difficulty">{"Difficulty"}</label>
+                <select id="puzzle_difficulty" ref=self.difficulty_ref.clone()>
+                    {option(Difficulty::Easy, "Easy (3 digits, a few clues)")}
+                    {option(Difficulty::Medium, "Medium (3 digits, more to work out)")}
+                    {option(Difficulty::Hard, "Hard (4 digits)")}
+                </select>
+                <input type="submit" value="New puzzle" onclick=self.link.callback(|_| Msg::NewPuzzle)/>
+            </div>
+        }
+    }
+}
+
+impl Component for PuzzleComponent {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let (difficulty, seed) = linked_puzzle();
+        Self {
+            link,
+            difficulty,
+            seed,
+            puzzle: with_game(generate(difficulty, seed)),
+            checked: None,
+            revealed: false,
+            answer_ref: NodeRef::default(),
+            difficulty_ref: NodeRef::default(),
+        }
+    }
+    fn update(&mut self, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Check => self.checked = Some(self.check()),
+            Msg::Reveal => self.revealed = true,
+            Msg::NewPuzzle => {
+                let difficulty = Difficulty::from_name(
+                    &self
+                        .difficulty_ref
+                        .cast::<HtmlSelectElement>()
+                        .unwrap()
+                        .value(),
+                )
+                .unwrap_or(self.difficulty);
+                self.show_puzzle(difficulty, random_seed());
+            }
+        }
+        true
+    }
+    fn change(&mut self, _: Self::Properties) -> bool {
+        false
+    }
+    fn view(&self) -> Html {
+        html! {
+            <div>
+                <h1>{"Lock puzzle"}</h1>
+                {self.render_puzzle()}
+                {self.render_new_puzzle()}
+                <p><a href="/">{"Back to the main game"}</a></p>
+            </div>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_puzzles_fit_their_difficulty_and_have_one_answer() {
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            for seed in 1..=3 {
+                let puzzle = generate(difficulty, seed).unwrap();
+                assert!(difficulty.fits(puzzle.clues.len(), puzzle.steps));
+                let verification = solver(difficulty.length()).verify(&puzzle.clues).unwrap();
+                let secret: Vec<char> = puzzle.secret.chars().collect();
+                assert_eq!(verification.solutions, vec![secret]);
+            }
+        }
+    }
+}
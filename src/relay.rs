@@ -0,0 +1,131 @@
+use crate::PbfStats;
+
+// Messages of the head-to-head mode, sent as websocket text in the same "key=value" lines as share links.
+// The relay only understands joining; everything else is passed between the two players as-is, so the relay never needs to know the rules.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RelayMessage {
+    // Player to relay. The player id lets someone who drops out take the same seat back, and seen is how many of the other player's messages they've already handled, so the relay only replays the rest.
+    Join {
+        room: String,
+        player: String,
+        seen: usize,
+    },
+
+    // Relay to player. Seat 0 guesses first. received is how many of this player's messages the relay already has, so the player only resends the rest.
+    Joined {
+        seat: usize,
+        received: usize,
+    },
+    Peer {
+        connected: bool,
+    },
+    Error {
+        message: String,
+    },
+
+    // Player to player. Ready is sent once the player's secret is set.
+    Ready {
+        length: usize,
+    },
+    Guess {
+        guess: String,
+    },
+    Result {
+        stats: PbfStats,
+    },
+    // Sent once the game is over, so the loser can see what they were guessing.
+    Reveal {
+        secret: String,
+    },
+}
+
+fn field<'a>(fields: &[(&str, &'a str)], key: &str) -> Result<&'a str, String> {
+    fields
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, value)| *value)
+        .ok_or(format!("Missing {}", key))
+}
+
+fn number_field<T: std::str::FromStr>(fields: &[(&str, &str)], key: &str) -> Result<T, String> {
+    let value = field(fields, key)?;
+    value
+        .parse()
+        .map_err(|_ignored| format!("{} is not a valid {}", value, key))
+}
+
+impl RelayMessage {
+    // Whether the relay passes this message on to the other player.
+    pub fn is_forwarded(&self) -> bool {
+        matches!(
+            self,
+            RelayMessage::Ready { .. }
+                | RelayMessage::Guess { .. }
+                | RelayMessage::Result { .. }
+                | RelayMessage::Reveal { .. }
+        )
+    }
+
+    pub fn encode(&self) -> String {
+        match self {
+            RelayMessage::Join { room, player, seen } => format!(
+                "kind=join\nroom={}\nplayer={}\nseen={}\n",
+                room, player, seen
+            ),
+            RelayMessage::Joined { seat, received } => {
+                format!("kind=joined\nseat={}\nreceived={}\n", seat, received)
+            }
+            RelayMessage::Peer { connected } => format!("kind=peer\nconnected={}\n", connected),
+            RelayMessage::Error { message } => format!("kind=error\nmessage={}\n", message),
+            RelayMessage::Ready { length } => format!("kind=ready\nlength={}\n", length),
+            RelayMessage::Guess { guess } => format!("kind=guess\nguess={}\n", guess),
+            RelayMessage::Result { stats } => {
+                format!("kind=result\np={}\nf={}\n", stats.p, stats.f)
+            }
+            RelayMessage::Reveal { secret } => format!("kind=reveal\nsecret={}\n", secret),
+        }
+    }
+
+    pub fn decode(s: &str) -> Result<Self, String> {
+        let fields: Vec<(&str, &str)> = s
+            .lines()
+            .map(|line| match line.find('=') {
+                Some(i) => Ok((&line[..i], &line[i + 1..])),
+                None => Err(format!("{} is not a key=value line", line)),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(match field(&fields, "kind")? {
+            "join" => RelayMessage::Join {
+                room: field(&fields, "room")?.to_string(),
+                player: field(&fields, "player")?.to_string(),
+                seen: number_field(&fields, "seen")?,
+            },
+            "joined" => RelayMessage::Joined {
+                seat: number_field(&fields, "seat")?,
+                received: number_field(&fields, "received")?,
+            },
+            "peer" => RelayMessage::Peer {
+                connected: number_field(&fields, "connected")?,
+            },
+            "error" => RelayMessage::Error {
+                message: field(&fields, "message")?.to_string(),
+            },
+            "ready" => RelayMessage::Ready {
+                length: number_field(&fields, "length")?,
+            },
+            "guess" => RelayMessage::Guess {
+                guess: field(&fields, "guess")?.to_string(),
+            },
+            "result" => RelayMessage::Result {
+                stats: PbfStats {
+                    p: number_field(&fields, "p")?,
+                    f: number_field(&fields, "f")?,
+                },
+            },
+            "reveal" => RelayMessage::Reveal {
+                secret: field(&fields, "secret")?.to_string(),
+            },
+            kind => return Err(format!("Unknown message kind {}", kind)),
+        })
+    }
+}
@@ -0,0 +1,225 @@
+use crate::game::{GameStatus, GuessState};
+use crate::render_guesses;
+use crate::rules::{split_field, Rules, SecretPayload};
+use yew::{html, Component, ComponentLink, Html};
+
+// Everything needed to play a finished game back: the rules, the secrets and the guesses in order.
+// Written in the same "key=value" lines as share links, with one guess= line per guess after the secret payload.
+pub struct Replay {
+    pub secrets: Vec<String>,
+    pub rules: Rules,
+    pub guesses: Vec<String>,
+}
+
+impl Replay {
+    pub fn from_game(guess_state: &GuessState) -> Self {
+        Self {
+            secrets: guess_state.secret_strings(),
+            rules: guess_state.rules.clone(),
+            guesses: guess_state.guesses.clone(),
+        }
+    }
+
+    fn payload(&self) -> SecretPayload {
+        SecretPayload {
+            secrets: self.secrets.clone(),
+            rules: self.rules.clone(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut encoded = self.payload().encode();
+        for guess in &self.guesses {
+            encoded.push_str(&format!("guess={}\n", guess));
+        }
+        encoded
+    }
+
+    pub fn decode(s: &str) -> Result<Self, ()> {
+        // The payload ignores keys it doesn't know, so it reads past the guesses.
+        let payload = SecretPayload::decode(s)?;
+        let length = payload.secrets.first().ok_or(())?.chars().count();
+        let mut guesses = Vec::new();
+        for line in s.lines() {
+            if let ("guess", guess) = split_field(line)? {
+                // Every guess played was the length of the secrets, so anything else means the link was mangled or edited.
+                if guess.chars().count() != length {
+                    return Err(());
+                }
+                guesses.push(guess.to_string());
+            }
+        }
+        Ok(Self {
+            secrets: payload.secrets,
+            rules: payload.rules,
+            guesses,
+        })
+    }
+
+    // Replays are only made once the game is over, so there's no need to hide the secrets. Base64 just keeps the link in one piece.
+    pub fn link(&self) -> String {
+        format!("/replay.html?{}", base64::encode(self.encode()))
+    }
+
+    // The game as it stood after the first count guesses.
+    fn game_after(&self, count: usize) -> GuessState {
+        let mut guess_state = GuessState::new(self.payload());
+        for guess in self.guesses.iter().take(count) {
+            if !guess_state.is_over() {
+                guess_state.guess(guess.clone());
+            }
+        }
+        guess_state
+    }
+
+    // How many possible secrets the solver had left after each guess, starting from before the first. One count per secret.
+    // None if there are too many possible secrets to enumerate.
+    fn candidate_counts(&self) -> Option<Vec<Vec<usize>>> {
+        let guess_state = self.game_after(self.guesses.len());
+        let mut states = guess_state
+            .secrets
+            .iter()
+            .map(|_| guess_state.solver_state())
+            .collect::<Option<Vec<_>>>()?;
+        let mut counts = vec![states.iter().map(|state| state.candidate_count()).collect()];
+        for guess in &guess_state.guesses {
+            for (state, secret) in states.iter_mut().zip(&guess_state.secrets) {
+                let scored = state.score(secret, guess.chars().collect());
+                state.add_guess(scored);
+            }
+            counts.push(states.iter().map(|state| state.candidate_count()).collect());
+        }
+        Some(counts)
+    }
+}
+
+// Reads the replay from the query portion of the url.
+fn linked_replay() -> Result<Replay, ()> {
+    let search = web_sys::window()
+        .ok_or(())?
+        .location()
+        .search()
+        .map_err(|_ignored| ())?;
+    let encoded = base64::decode(search.strip_prefix('?').ok_or(())?).map_err(|_ignored| ())?;
+    Replay::decode(std::str::from_utf8(&encoded).map_err(|_ignored| ())?)
+}
+
+// Steps through a finished game one guess at a time.
+pub struct ReplayComponent {
+    link: ComponentLink<Self>,
+    // None if the link couldn't be read.
+    replay: Option<Replay>,
+    candidate_counts: Option<Vec<Vec<usize>>>,
+    // How many guesses have been played back.
+    step: usize,
+    game: Option<GuessState>,
+}
+
+pub enum Msg {
+    First,
+    Previous,
+    Next,
+    Last,
+}
+
+impl ReplayComponent {
+    fn show_step(&mut self, step: usize) {
+        if let Some(replay) = &self.replay {
+            let game = replay.game_after(step);
+            self.step = game.guesses.len();
+            self.game = Some(game);
+        }
+    }
+
+    fn render_candidates(&self) -> Html {
+        let counts = match &self.candidate_counts {
+            Some(counts) => counts,
+            None => return html! {<p>{"There are too many possible secrets to count."}</p>},
+        };
+        let counts: Vec<String> = counts[self.step]
+            .iter()
+            .map(|count| count.to_string())
+            .collect();
+        html! {<p class="candidates">{format!("Possible secrets left: {}", counts.join(", "))}</p>}
+    }
+
+    fn render_game(&self, replay: &Replay, game: &GuessState) -> Html {
+        let outcome = match game.status() {
+            GameStatus::Won => format!("Solved in {} guesses.", game.guesses.len()),
+            GameStatus::Lost => "Ran out of guesses.".to_string(),
+            _ if self.step == replay.guesses.len() => "Gave up.".to_string(),
+            _ => format!("Guess {} of {}", self.step, replay.guesses.len()),
+        };
+        let reveal_html = if self.step == replay.guesses.len() {
+            html! {<p class="reveal">{format!("The answer was {}", game.secret_string())}</p>}
+        } else {
+            html! {}
+        };
+        html! {
+            <div class="guesses">
+                <p class="status">{outcome}</p>
+                {render_guesses(game)}
+                {self.render_candidates()}
+                {reveal_html}
+                <input type="submit" value="First" disabled=self.step == 0 onclick=self.link.callback(|_| Msg::First)/>
+                <input type="submit" value="Previous" disabled=self.step == 0 onclick=self.link.callback(|_| Msg::Previous)/>
+                <input type="submit" value="Next" disabled=self.step == replay.guesses.len() onclick=self.link.callback(|_| Msg::Next)/>
+                <input type="submit" value="Last" disabled=self.step == replay.guesses.len() onclick=self.link.callback(|_| Msg::Last)/>
+            </div>
+        }
+    }
+}
+
+impl Component for ReplayComponent {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        // Guesses after the game was over can't have been played, so a link that has any is cut short.
+        let replay = linked_replay().ok().map(|mut replay| {
+            replay.guesses = replay.game_after(replay.guesses.len()).guesses;
+            replay
+        });
+        let candidate_counts = replay.as_ref().and_then(|replay| replay.candidate_counts());
+        let mut component = Self {
+            link,
+            replay,
+            candidate_counts,
+            step: 0,
+            game: None,
+        };
+        component.show_step(0);
+        component
+    }
+    fn update(&mut self, msg: Self::Message) -> bool {
+        let last = self
+            .replay
+            .as_ref()
+            .map(|replay| replay.guesses.len())
+            .unwrap_or(0);
+        let step = match msg {
+            Msg::First => 0,
+            Msg::Previous => self.step.saturating_sub(1),
+            Msg::Next => (self.step + 1).min(last),
+            Msg::Last => last,
+        };
+        self.show_step(step);
+        true
+    }
+    fn change(&mut self, _: Self::Properties) -> bool {
+        false
+    }
+    fn view(&self) -> Html {
+        let game_html = match (&self.replay, &self.game) {
+            (Some(replay), Some(game)) => self.render_game(replay, game),
+            _ => html! {<p>{"Invalid url"}</p>},
+        };
+        html! {
+            <div>
+                <h1>{"Replay"}</h1>
+                {game_html}
+                <p><a href="/">{"Back to the main game"}</a></p>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use crate::mastermind;
+use crate::pbf::{Feedback, Scoring};
+use crate::words::{is_word, word_lengths};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Variant {
+    // Any symbols, scored as a set.
+    #[default]
+    PicoBagelFermi,
+    // Four pegs out of six colors, repeats allowed and scored as a multiset.
+    Mastermind,
+    // Four distinct digits, with results written as bulls (A) and cows (B).
+    BullsAndCows,
+    // Secrets and guesses are lowercase words from the bundled word lists, scored as a multiset.
+    Words,
+}
+
+impl Variant {
+    fn name(self) -> &'static str {
+        match self {
+            Variant::PicoBagelFermi => "pbf",
+            Variant::Mastermind => "mastermind",
+            Variant::BullsAndCows => "bulls_and_cows",
+            Variant::Words => "words",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<Self, ()> {
+        match name {
+            "pbf" => Ok(Variant::PicoBagelFermi),
+            "mastermind" => Ok(Variant::Mastermind),
+            "bulls_and_cows" => Ok(Variant::BullsAndCows),
+            "words" => Ok(Variant::Words),
+            _ => Err(()),
+        }
+    }
+
+    pub fn scoring(self) -> Scoring {
+        match self {
+            Variant::PicoBagelFermi | Variant::BullsAndCows => Scoring::Set,
+            Variant::Mastermind | Variant::Words => Scoring::Multiset,
+        }
+    }
+
+    // The symbols secrets must be drawn from, if the variant restricts them.
+    pub fn guess_space(self) -> Option<Vec<char>> {
+        match self {
+            Variant::PicoBagelFermi => None,
+            Variant::Mastermind => Some(
+                mastermind::COLORS
+                    .iter()
+                    .map(|color| color.symbol)
+                    .collect(),
+            ),
+            Variant::BullsAndCows => Some(('0'..='9').collect()),
+            Variant::Words => Some(('a'..='z').collect()),
+        }
+    }
+
+    pub fn validate_secret(self, secret: &str) -> Result<(), String> {
+        match self {
+            Variant::PicoBagelFermi => {}
+            Variant::Mastermind => {
+                if secret.chars().count() != mastermind::CODE_LENGTH {
+                    return Err(format!(
+                        "{} must be {} colors long",
+                        secret,
+                        mastermind::CODE_LENGTH
+                    ));
+                }
+            }
+            Variant::BullsAndCows => {
+                let digits: Vec<char> = secret.chars().collect();
+                if digits.len() != 4 || (1..digits.len()).any(|i| digits[..i].contains(&digits[i]))
+                {
+                    return Err(format!("{} must be four different digits", secret));
+                }
+            }
+            Variant::Words => {
+                if !word_lengths().contains(&secret.chars().count()) {
+                    let lengths: Vec<String> = word_lengths()
+                        .iter()
+                        .map(|length| length.to_string())
+                        .collect();
+                    return Err(format!(
+                        "Words must be {} letters long",
+                        lengths.join(" or ")
+                    ));
+                }
+                if !is_word(secret) {
+                    return Err(format!("{} is not in the word list", secret));
+                }
+            }
+        }
+        if let Some(guess_space) = self.guess_space() {
+            if let Some(c) = secret.chars().find(|c| !guess_space.contains(c)) {
+                return Err(format!(
+                    "{} is not one of {}",
+                    c,
+                    guess_space.iter().collect::<String>()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Whether guesses have to fit the results of the guesses before them, i.e. could still be the secret.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Consistency {
+    #[default]
+    Unchecked,
+    // The guesser is told when a guess can't be the secret, but it's still played.
+    Soft,
+    // Guesses that can't be the secret are turned away.
+    Hard,
+}
+
+impl Consistency {
+    fn name(self) -> &'static str {
+        match self {
+            Consistency::Unchecked => "unchecked",
+            Consistency::Soft => "soft",
+            Consistency::Hard => "hard",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<Self, ()> {
+        match name {
+            "unchecked" => Ok(Consistency::Unchecked),
+            "soft" => Ok(Consistency::Soft),
+            "hard" => Ok(Consistency::Hard),
+            _ => Err(()),
+        }
+    }
+}
+
+// Settings chosen by whoever created the secret. These travel with the secret in the share link.
+#[derive(Clone)]
+pub struct Rules {
+    // The guesser loses once they have made this many guesses without finding the secret.
+    pub max_guesses: Option<usize>,
+
+    pub variant: Variant,
+
+    pub feedback: Feedback,
+
+    pub consistency: Consistency,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            max_guesses: None,
+            variant: Variant::default(),
+            feedback: Feedback::Counts,
+            consistency: Consistency::default(),
+        }
+    }
+}
+
+impl Rules {
+    // One "key=value" line for each setting that isn't the default, each terminated with a newline.
+    pub fn encode(&self) -> String {
+        let mut encoded = String::new();
+        if let Some(max_guesses) = self.max_guesses {
+            encoded.push_str(&format!("max_guesses={}\n", max_guesses));
+        }
+        if self.variant != Variant::default() {
+            encoded.push_str(&format!("variant={}\n", self.variant.name()));
+        }
+        if self.feedback == Feedback::Positions {
+            encoded.push_str("feedback=positions\n");
+        }
+        if self.consistency != Consistency::default() {
+            encoded.push_str(&format!("consistency={}\n", self.consistency.name()));
+        }
+        encoded
+    }
+
+    // Reads one "key=value" line written by encode. Keys that aren't rules are left alone, so rules can share lines with other fields.
+    pub fn decode_field(&mut self, key: &str, value: &str) -> Result<(), ()> {
+        match key {
+            "max_guesses" => self.max_guesses = Some(value.parse().map_err(|_ignored| ())?),
+            "variant" => self.variant = Variant::from_name(value)?,
+            "feedback" => {
+                self.feedback = match value {
+                    "counts" => Feedback::Counts,
+                    "positions" => Feedback::Positions,
+                    _ => return Err(()),
+                }
+            }
+            "consistency" => self.consistency = Consistency::from_name(value)?,
+            // Ignore anything we don't understand so older versions can still play newer links.
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+// Everything that gets encrypted into a share link.
+pub struct SecretPayload {
+    // Usually just one, but games can have the guesser find several at once.
+    pub secrets: Vec<String>,
+    pub rules: Rules,
+}
+
+// Splits a line of the "key=value" format used by share links.
+pub fn split_field(line: &str) -> Result<(&str, &str), ()> {
+    match line.find('=') {
+        Some(i) => Ok((&line[..i], &line[i + 1..])),
+        None => Err(()),
+    }
+}
+
+impl SecretPayload {
+    // One "key=value" line per field, each terminated with a newline.
+    pub fn encode(&self) -> String {
+        let mut encoded: String = self
+            .secrets
+            .iter()
+            .map(|secret| format!("secret={}\n", secret))
+            .collect();
+        encoded.push_str(&self.rules.encode());
+        encoded
+    }
+
+    // Links made before rules existed hold nothing but the secret, so anything without a newline is a bare secret played under the default rules.
+    pub fn decode(s: &str) -> Result<Self, ()> {
+        if !s.contains('\n') {
+            return Ok(Self {
+                secrets: vec![s.to_string()],
+                rules: Rules::default(),
+            });
+        }
+        let mut secrets = Vec::new();
+        let mut rules = Rules::default();
+        for line in s.lines() {
+            let (key, value) = split_field(line)?;
+            match key {
+                "secret" => secrets.push(value.to_string()),
+                _ => rules.decode_field(key, value)?,
+            }
+        }
+        // Every secret is guessed with the same guesses, so they have to be the same length.
+        let length = secrets.first().ok_or(())?.chars().count();
+        if secrets
+            .iter()
+            .any(|secret| secret.chars().count() != length)
+        {
+            return Err(());
+        }
+        Ok(Self { secrets, rules })
+    }
+}
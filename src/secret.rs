@@ -1,5 +1,7 @@
+use crate::pbf::{PositionResult, Scoring};
 use crate::PbfStats;
-use std::collections::HashSet;
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::hash::Hash;
@@ -30,22 +32,96 @@ impl<T: Clone + Hash + Eq> Secret<T> {
         }
     }
 
-    pub fn compare(&self, guess: &Vec<T>) -> PbfStats {
-        let f = self
-            .in_order
+    // The guess can be a slice (by reference) or any other iterator of symbols, e.g. the chars of a string, so nothing needs to be collected first.
+    pub fn compare<I>(&self, guess: I) -> PbfStats
+    where
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        let mut f = 0;
+        let mut matched = 0;
+        for (i, symbol) in guess.into_iter().enumerate() {
+            let symbol = symbol.borrow();
+            if self.in_order.get(i) == Some(symbol) {
+                f += 1;
+            }
+            if self.indexed.contains(symbol) {
+                matched += 1;
+            }
+        }
+        PbfStats { f, p: matched - f }
+    }
+
+    pub fn compare_multiset<I>(&self, guess: I) -> PbfStats
+    where
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        let mut unmatched: HashMap<&T, usize> = HashMap::new();
+        for symbol in &self.in_order {
+            *unmatched.entry(symbol).or_insert(0) += 1;
+        }
+        let mut f = 0;
+        let mut matched = 0;
+        for (i, symbol) in guess.into_iter().enumerate() {
+            let symbol = symbol.borrow();
+            if self.in_order.get(i) == Some(symbol) {
+                f += 1;
+            }
+            if let Some(count) = unmatched.get_mut(symbol) {
+                if *count > 0 {
+                    *count -= 1;
+                    matched += 1;
+                }
+            }
+        }
+        PbfStats { f, p: matched - f }
+    }
+
+    pub fn compare_with<I>(&self, guess: I, scoring: Scoring) -> PbfStats
+    where
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        match scoring {
+            Scoring::Set => self.compare(guess),
+            Scoring::Multiset => self.compare_multiset(guess),
+        }
+    }
+
+    // Wordle-style: exact matches are marked first, then each remaining symbol in the secret can mark one misplaced guess symbol as present.
+    pub fn compare_positions<I>(&self, guess: I) -> Vec<PositionResult>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        // Exact matches have to be known before any symbol is marked present, so this one does need the whole guess up front.
+        let guess: Vec<I::Item> = guess.into_iter().collect();
+        let mut unmatched: HashMap<&T, usize> = HashMap::new();
+        for (secret_symbol, guess_symbol) in self.in_order.iter().zip(guess.iter()) {
+            if secret_symbol != guess_symbol.borrow() {
+                *unmatched.entry(secret_symbol).or_insert(0) += 1;
+            }
+        }
+        for secret_symbol in self.in_order.iter().skip(guess.len()) {
+            *unmatched.entry(secret_symbol).or_insert(0) += 1;
+        }
+        guess
             .iter()
             .enumerate()
-            .zip(guess.iter().enumerate())
-            .filter(|(a, b)| a == b)
-            .count();
-        let p = guess
-            .iter()
-            .filter(|guess| self.indexed.contains(guess))
-            .count()
-            - f;
-        PbfStats {
-            f: f as i32,
-            p: p as i32,
-        }
+            .map(|(i, guess_symbol)| {
+                let guess_symbol = guess_symbol.borrow();
+                if self.in_order.get(i) == Some(guess_symbol) {
+                    return PositionResult::Exact;
+                }
+                match unmatched.get_mut(guess_symbol) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        PositionResult::Present
+                    }
+                    _ => PositionResult::Absent,
+                }
+            })
+            .collect()
     }
 }
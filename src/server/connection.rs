@@ -0,0 +1,75 @@
+// Bookkeeping shared by the websocket servers, which track who is connected to each room or lobby.
+use crate::server::websocket::WebSocket;
+use std::time::{Duration, Instant};
+
+// How long a room or lobby is kept once nobody is connected to it, so players who drop out together can still come back to their game.
+const EMPTY_LIFETIME: Duration = Duration::from_secs(30 * 60);
+
+// Which connection a player is on, if they're connected. The id tells a connection apart from the one that replaced it when the player reconnected.
+#[derive(Default)]
+pub struct Connection(Option<(usize, WebSocket)>);
+
+impl Connection {
+    pub fn connect(&mut self, id: usize, socket: WebSocket) {
+        self.0 = Some((id, socket));
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.0.is_some()
+    }
+
+    // Whether the player is still on this connection rather than a newer one.
+    pub fn is(&self, id: usize) -> bool {
+        matches!(&self.0, Some((current, _)) if *current == id)
+    }
+
+    // Forgets the connection unless the player has already reconnected on a new one, which takes priority. Whether it did.
+    pub fn disconnect(&mut self, id: usize) -> bool {
+        let current = self.is(id);
+        if current {
+            self.0 = None;
+        }
+        current
+    }
+}
+
+// When everyone left a room or lobby, if nobody is connected to it.
+#[derive(Default)]
+pub struct Vacancy(Option<Instant>);
+
+impl Vacancy {
+    // Call whenever someone connects or disconnects. An empty room keeps the time it first became empty.
+    pub fn update(&mut self, occupied: bool) {
+        if occupied {
+            self.0 = None;
+        } else if self.0.is_none() {
+            self.0 = Some(Instant::now());
+        }
+    }
+
+    // Whether the room or lobby has been empty long enough to be forgotten.
+    pub fn expired(&self, now: Instant) -> bool {
+        matches!(self.0, Some(since) if now.duration_since(since) > EMPTY_LIFETIME)
+    }
+}
+
+// Messages gathered while the servers' shared state is locked, to be sent once it's unlocked. Sending can take as long as the write timeout, which would hold up every other connection waiting on the lock.
+#[derive(Default)]
+pub struct Outbox(Vec<(WebSocket, String)>);
+
+impl Outbox {
+    pub fn push(&mut self, connection: &Connection, message: String) {
+        if let Some((_, socket)) = &connection.0 {
+            if let Ok(socket) = socket.try_clone() {
+                self.0.push((socket, message));
+            }
+        }
+    }
+
+    // A dropped connection shows up when reading from it, which takes care of cleaning up, so a failed send can be ignored.
+    pub fn send(self) {
+        for (mut socket, message) in self.0 {
+            let _ignored = socket.send(&message);
+        }
+    }
+}
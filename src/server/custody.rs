@@ -0,0 +1,323 @@
+// Keeps secrets on the server so they never reach the guesser's browser, and scores guesses against them.
+// Everything is kept in memory, so games are lost when the server stops.
+use crate::custody::{encode_scored, Session};
+use crate::game::{GameStatus, GuessState, ScoredGuess};
+use crate::pbf::Feedback;
+use crate::rules::SecretPayload;
+use crate::server::http::{read_request, set_timeouts, write_response, Request};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+// Games and sessions nobody has used for this long are forgotten, so abandoned ones don't pile up.
+const LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Client tokens are made by the browser, so there's no telling how long one might be.
+const MAX_CLIENT_LENGTH: usize = 64;
+
+struct GameState {
+    // The encoded SecretPayload.
+    payload: String,
+    // Starting a session or guessing in one counts as a use.
+    last_used: Instant,
+}
+
+struct SessionState {
+    game: String,
+    guess_state: GuessState,
+    last_used: Instant,
+}
+
+#[derive(Default)]
+struct Store {
+    games: HashMap<String, GameState>,
+    // Each guesser's attempt at a game, by session id.
+    sessions: HashMap<String, SessionState>,
+    // The session id for each game and client, so a guesser who comes back to a game carries on where they left off instead of starting over with a fresh set of guesses.
+    client_sessions: HashMap<(String, String), String>,
+}
+
+impl Store {
+    // The session's game, if it's still kept, counting this as a use of both.
+    fn session(&mut self, id: &str) -> Option<&mut GuessState> {
+        let now = Instant::now();
+        let session = self.sessions.get_mut(id)?;
+        session.last_used = now;
+        if let Some(game) = self.games.get_mut(&session.game) {
+            game.last_used = now;
+        }
+        Some(&mut session.guess_state)
+    }
+
+    // Games and sessions are only added by creating a game or starting a session, which is a good time to get rid of old ones.
+    fn forget_unused(&mut self, now: Instant) {
+        self.games
+            .retain(|_, game| now.duration_since(game.last_used) < LIFETIME);
+        self.sessions
+            .retain(|_, session| now.duration_since(session.last_used) < LIFETIME);
+        let sessions = &self.sessions;
+        self.client_sessions
+            .retain(|_, session| sessions.contains_key(session));
+    }
+}
+
+type Response = (u16, String);
+
+fn error(status: u16, message: &str) -> Response {
+    (status, message.to_string())
+}
+
+// Ids need to be hard to guess, so nobody can find someone else's game. std's randomly seeded hasher isn't a cryptographic generator, but it's good enough for a game.
+fn new_id() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+    hasher.write_u128(nanos);
+    format!("{:016x}", hasher.finish())
+}
+
+fn create_game(store: &mut Store, body: &str) -> Response {
+    let payload = match SecretPayload::decode(body) {
+        Ok(payload) => payload,
+        Err(()) => return error(400, "That isn't a valid game"),
+    };
+    for secret in &payload.secrets {
+        if let Err(e) = payload.rules.variant.validate_secret(secret) {
+            return (400, e);
+        }
+    }
+    let now = Instant::now();
+    store.forget_unused(now);
+    let id = new_id();
+    store.games.insert(
+        id.clone(),
+        GameState {
+            payload: payload.encode(),
+            last_used: now,
+        },
+    );
+    (200, id)
+}
+
+// Everything the guesser needs to pick the game up from where the session is.
+fn describe(id: &str, guess_state: &GuessState) -> Session {
+    let count = guess_state.guesses.len();
+    Session {
+        id: id.to_string(),
+        secret_count: guess_state.secret_count,
+        secret_length: guess_state.secret_length,
+        rules: guess_state.rules.clone(),
+        status: guess_state.status(),
+        secrets: scored(guess_state, None).secrets,
+        played: guess_state
+            .guesses
+            .iter()
+            .enumerate()
+            .map(|(i, guess)| {
+                let mut scored = scored(guess_state, Some(guess));
+                // Only the last guess can have won or lost, and the secrets are told once, with the session.
+                if i + 1 < count || scored.status == GameStatus::GaveUp {
+                    scored.status = GameStatus::InProgress;
+                }
+                scored.secrets = Vec::new();
+                (guess.clone(), scored)
+            })
+            .collect(),
+    }
+}
+
+// Each client gets one session per game, so giving up or running out of guesses can't be undone by starting again.
+fn start_session(store: &mut Store, game: &str, client: &str) -> Response {
+    let client = client.trim();
+    if client.is_empty() || client.len() > MAX_CLIENT_LENGTH {
+        return error(400, "That isn't a valid client");
+    }
+    let now = Instant::now();
+    store.forget_unused(now);
+    let key = (game.to_string(), client.to_string());
+    if let Some(id) = store.client_sessions.get(&key).cloned() {
+        if let Some(guess_state) = store.session(&id) {
+            return (200, describe(&id, guess_state).encode());
+        }
+    }
+    let payload = match store
+        .games
+        .get(game)
+        .and_then(|game| SecretPayload::decode(&game.payload).ok())
+    {
+        Some(payload) => payload,
+        None => return error(404, "There's no such game"),
+    };
+    let guess_state = GuessState::new(payload);
+    let id = new_id();
+    let session = describe(&id, &guess_state);
+    store.client_sessions.insert(key, id.clone());
+    store.sessions.insert(
+        id,
+        SessionState {
+            game: game.to_string(),
+            guess_state,
+            last_used: now,
+        },
+    );
+    (200, session.encode())
+}
+
+// The secrets only go back once the game is over.
+fn scored(guess_state: &GuessState, guess: Option<&str>) -> ScoredGuess {
+    ScoredGuess {
+        results: guess
+            .map(|guess| guess_state.compare(guess))
+            .unwrap_or_default(),
+        positions: guess
+            .filter(|_| guess_state.rules.feedback == Feedback::Positions)
+            .map(|guess| guess_state.compare_positions(guess)),
+        status: guess_state.status(),
+        secrets: if guess_state.is_over() {
+            guess_state.secret_strings()
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+fn guess(store: &mut Store, session: &str, guess: &str) -> Response {
+    let guess_state = match store.session(session) {
+        Some(guess_state) => guess_state,
+        None => return error(404, "There's no such game"),
+    };
+    if guess_state.is_over() {
+        return error(400, "The game is over");
+    }
+    if let Err(e) = guess_state.validate_guess(guess) {
+        return (400, e);
+    }
+    guess_state.guess(guess.to_string());
+    (200, encode_scored(&scored(guess_state, Some(guess))))
+}
+
+fn give_up(store: &mut Store, session: &str) -> Response {
+    match store.session(session) {
+        Some(guess_state) => {
+            guess_state.give_up();
+            (200, encode_scored(&scored(guess_state, None)))
+        }
+        None => error(404, "There's no such game"),
+    }
+}
+
+fn respond(store: &Mutex<Store>, request: &Request) -> Response {
+    let mut store = store.lock().unwrap();
+    let path: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    match (request.method.as_str(), path.as_slice()) {
+        // Browsers ask before some cross-origin requests.
+        ("OPTIONS", _) => (204, String::new()),
+        ("POST", ["games"]) => create_game(&mut store, &request.body),
+        ("POST", ["games", game, "sessions"]) => start_session(&mut store, game, &request.body),
+        ("POST", ["sessions", session, "guesses"]) => guess(&mut store, session, &request.body),
+        ("POST", ["sessions", session, "give_up"]) => give_up(&mut store, session),
+        (_, ["games"]) | (_, ["games", _, "sessions"]) | (_, ["sessions", _, _]) => {
+            error(405, "Only POST is supported")
+        }
+        _ => error(404, "Not found"),
+    }
+}
+
+pub fn serve(address: &str) {
+    let listener = TcpListener::bind(address).expect("Could not listen on the address");
+    println!("Custody server listening on http://{}", address);
+    let store = Arc::new(Mutex::new(Store::default()));
+    for stream in listener.incoming().flatten() {
+        let store = store.clone();
+        thread::spawn(move || {
+            if set_timeouts(&stream).is_err() {
+                return;
+            }
+            let (status, body) = match read_request(&stream) {
+                Ok(request) => respond(&store, &request),
+                Err(e) => (400, e.to_string()),
+            };
+            // Nothing useful can be done if the guesser has gone away.
+            let _ignored = write_response(&stream, status, &body);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::PbfStats;
+
+    fn new_game(store: &mut Store) -> String {
+        let payload = SecretPayload {
+            secrets: vec!["123".to_string()],
+            rules: Rules {
+                max_guesses: Some(2),
+                ..Rules::default()
+            },
+        };
+        let (status, game) = create_game(store, &payload.encode());
+        assert_eq!(status, 200);
+        game
+    }
+
+    fn session(store: &mut Store, game: &str, client: &str) -> Session {
+        let (status, body) = start_session(store, game, client);
+        assert_eq!(status, 200, "{}", body);
+        Session::decode(&body).unwrap()
+    }
+
+    #[test]
+    fn coming_back_resumes_the_same_session() {
+        let mut store = Store::default();
+        let game = new_game(&mut store);
+        let first = session(&mut store, &game, "client");
+        assert_eq!(guess(&mut store, &first.id, "456").0, 200);
+        let again = session(&mut store, &game, "client");
+        assert_eq!(again.id, first.id);
+        assert_eq!(again.status, GameStatus::InProgress);
+        assert!(again.secrets.is_empty());
+        assert_eq!(again.played.len(), 1);
+        assert_eq!(again.played[0].0, "456");
+        assert_eq!(again.played[0].1.results, vec![PbfStats { f: 0, p: 0 }]);
+        // The guess already made still counts against the limit.
+        assert_eq!(guess(&mut store, &again.id, "321").0, 200);
+        assert_eq!(guess(&mut store, &again.id, "123").0, 400);
+    }
+
+    #[test]
+    fn giving_up_cant_be_undone_by_starting_again() {
+        let mut store = Store::default();
+        let game = new_game(&mut store);
+        let first = session(&mut store, &game, "client");
+        assert_eq!(give_up(&mut store, &first.id).0, 200);
+        let again = session(&mut store, &game, "client");
+        assert_eq!(again.id, first.id);
+        assert_eq!(again.status, GameStatus::GaveUp);
+        assert_eq!(again.secrets, vec!["123".to_string()]);
+        assert_eq!(guess(&mut store, &again.id, "123").0, 400);
+        // Someone else playing the same game gets a session of their own, without the secrets.
+        let other = session(&mut store, &game, "other");
+        assert_ne!(other.id, first.id);
+        assert!(other.secrets.is_empty());
+    }
+
+    #[test]
+    fn unused_games_are_forgotten() {
+        let mut store = Store::default();
+        let game = new_game(&mut store);
+        session(&mut store, &game, "client");
+        store.forget_unused(Instant::now() + LIFETIME);
+        assert!(store.games.is_empty());
+        assert!(store.sessions.is_empty());
+        assert!(store.client_sessions.is_empty());
+        assert_eq!(start_session(&mut store, &game, "client").0, 404);
+    }
+}
@@ -0,0 +1,6 @@
+// Shared by the native server binaries in src/bin. None of it is built for the browser.
+pub mod connection;
+pub mod custody;
+pub mod http;
+pub mod sha1;
+pub mod websocket;
@@ -0,0 +1,172 @@
+use crate::server::http::set_timeouts;
+use crate::server::sha1::sha1;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::{Arc, Mutex};
+
+// Fixed by the websocket spec (RFC 6455).
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+// No message in these games comes close, so anything bigger is someone misbehaving.
+const MAX_MESSAGE_BYTES: u64 = 64 * 1024;
+const MAX_HANDSHAKE_BYTES: usize = 8 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xa;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// What the server answers with to prove it understood the handshake.
+pub fn accept_key(key: &str) -> String {
+    base64::encode(sha1(format!("{}{}", key, HANDSHAKE_GUID).as_bytes()))
+}
+
+// A websocket connection accepted by one of the servers. Only text messages are supported.
+pub struct WebSocket {
+    stream: TcpStream,
+    // Shared by every handle to the connection, so frames sent from different threads never interleave.
+    writer: Arc<Mutex<TcpStream>>,
+}
+
+impl WebSocket {
+    // Reads the HTTP upgrade request from a new connection and answers it.
+    // A client gets as long to finish the handshake as an HTTP client gets for its request. After that it may stay quiet for as long as it likes, but sends to it still time out, so one that stops reading can't hold up whoever is sending.
+    pub fn accept(mut stream: TcpStream) -> io::Result<Self> {
+        set_timeouts(&stream)?;
+        // Read a byte at a time so nothing after the request is swallowed by a buffer.
+        let mut request = Vec::new();
+        let mut byte = [0u8; 1];
+        while !request.ends_with(b"\r\n\r\n") {
+            if request.len() > MAX_HANDSHAKE_BYTES {
+                return Err(invalid("Handshake is too long"));
+            }
+            stream.read_exact(&mut byte)?;
+            request.push(byte[0]);
+        }
+        let request = String::from_utf8_lossy(&request);
+        let key = request
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("Sec-WebSocket-Key"))
+            .map(|(_, value)| value.trim().to_string())
+            .ok_or_else(|| invalid("Not a websocket handshake"))?;
+        write!(
+            stream,
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+            accept_key(&key)
+        )?;
+        stream.set_read_timeout(None)?;
+        let writer = Arc::new(Mutex::new(stream.try_clone()?));
+        Ok(Self { stream, writer })
+    }
+
+    // Another handle to the same connection, so one thread can read while others send.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self {
+            stream: self.stream.try_clone()?,
+            writer: self.writer.clone(),
+        })
+    }
+
+    fn read_frame(&mut self) -> io::Result<(bool, u8, Vec<u8>)> {
+        let mut header = [0u8; 2];
+        self.stream.read_exact(&mut header)?;
+        let fin = header[0] & 0x80 != 0;
+        let opcode = header[0] & 0x0f;
+        let masked = header[1] & 0x80 != 0;
+        let length = match header[1] & 0x7f {
+            126 => {
+                let mut length = [0u8; 2];
+                self.stream.read_exact(&mut length)?;
+                u16::from_be_bytes(length) as u64
+            }
+            127 => {
+                let mut length = [0u8; 8];
+                self.stream.read_exact(&mut length)?;
+                u64::from_be_bytes(length)
+            }
+            length => length as u64,
+        };
+        if length > MAX_MESSAGE_BYTES {
+            return Err(invalid("Message is too long"));
+        }
+        let mut mask = [0u8; 4];
+        if masked {
+            self.stream.read_exact(&mut mask)?;
+        }
+        let mut payload = vec![0u8; length as usize];
+        self.stream.read_exact(&mut payload)?;
+        if masked {
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+        }
+        Ok((fin, opcode, payload))
+    }
+
+    fn write_frame(&self, opcode: u8, payload: &[u8]) -> io::Result<()> {
+        // Frames from the server are never masked.
+        let mut frame = vec![0x80 | opcode];
+        match payload.len() {
+            length if length < 126 => frame.push(length as u8),
+            length if length <= u16::MAX as usize => {
+                frame.push(126);
+                frame.extend_from_slice(&(length as u16).to_be_bytes());
+            }
+            length => {
+                frame.push(127);
+                frame.extend_from_slice(&(length as u64).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(payload);
+        // A thread that panicked while sending may have left half a frame behind, which ruins the connection anyway.
+        let result = self
+            .writer
+            .lock()
+            .map_err(|_ignored| invalid("Connection is broken"))
+            .and_then(|mut writer| writer.write_all(&frame));
+        if result.is_err() {
+            // Part of the frame may have gone out, so nothing more can be sent. Closing the connection ends the read loop, which cleans up after it.
+            let _ignored = self.stream.shutdown(Shutdown::Both);
+        }
+        result
+    }
+
+    // The next text message, or None once the other end has closed the connection. Pings are answered along the way.
+    pub fn read_message(&mut self) -> io::Result<Option<String>> {
+        let mut message = Vec::new();
+        loop {
+            let (fin, opcode, payload) = self.read_frame()?;
+            match opcode {
+                OPCODE_TEXT | OPCODE_CONTINUATION => {
+                    message.extend_from_slice(&payload);
+                    if message.len() as u64 > MAX_MESSAGE_BYTES {
+                        return Err(invalid("Message is too long"));
+                    }
+                    if fin {
+                        return String::from_utf8(message)
+                            .map(Some)
+                            .map_err(|_ignored| invalid("Message is not utf-8"));
+                    }
+                }
+                OPCODE_CLOSE => {
+                    // Closing can't fail in a way that matters any more.
+                    let _ignored = self.write_frame(OPCODE_CLOSE, &payload);
+                    return Ok(None);
+                }
+                OPCODE_PING => self.write_frame(OPCODE_PONG, &payload)?,
+                OPCODE_PONG => {}
+                _ => return Err(invalid("Only text messages are supported")),
+            }
+        }
+    }
+
+    pub fn send(&mut self, message: &str) -> io::Result<()> {
+        self.write_frame(OPCODE_TEXT, message.as_bytes())
+    }
+}
@@ -0,0 +1,100 @@
+// The browser's side of a connection to one of the websocket servers in src/bin, shared by the head to head and group modes.
+use std::time::Duration;
+use yew::format::Text;
+use yew::services::timeout::{TimeoutService, TimeoutTask};
+use yew::services::websocket::{WebSocketService, WebSocketStatus, WebSocketTask};
+use yew::Callback;
+
+// Reconnecting waits twice as long after each failed attempt, up to this long.
+const MAX_RECONNECT_DELAY_SECONDS: u64 = 16;
+
+// A connection that is tried again whenever it drops, until the server lets us in.
+pub struct ServerLink {
+    url: &'static str,
+    received: Callback<Text>,
+    status: Callback<WebSocketStatus>,
+    // Told when it's time to call connect again.
+    reconnect_due: Callback<()>,
+
+    socket: Option<WebSocketTask>,
+    // Set between the server letting us in and the connection dropping.
+    joined: bool,
+    reconnect: Option<TimeoutTask>,
+    failed_connections: u32,
+}
+
+impl ServerLink {
+    pub fn new(
+        url: &'static str,
+        received: Callback<Text>,
+        status: Callback<WebSocketStatus>,
+        reconnect_due: Callback<()>,
+    ) -> Self {
+        Self {
+            url,
+            received,
+            status,
+            reconnect_due,
+            socket: None,
+            joined: false,
+            reconnect: None,
+            failed_connections: 0,
+        }
+    }
+
+    // If the connection can't even be started, another attempt is scheduled and the reason returned.
+    pub fn connect(&mut self) -> Result<(), String> {
+        self.reconnect = None;
+        self.joined = false;
+        match WebSocketService::connect_text(self.url, self.received.clone(), self.status.clone()) {
+            Ok(socket) => {
+                self.socket = Some(socket);
+                Ok(())
+            }
+            Err(e) => {
+                self.schedule_reconnect();
+                Err(e.to_string())
+            }
+        }
+    }
+
+    pub fn schedule_reconnect(&mut self) {
+        self.socket = None;
+        self.joined = false;
+        if self.reconnect.is_some() {
+            return;
+        }
+        let delay = 2u64
+            .saturating_pow(self.failed_connections)
+            .min(MAX_RECONNECT_DELAY_SECONDS);
+        self.failed_connections += 1;
+        self.reconnect = Some(TimeoutService::spawn(
+            Duration::from_secs(delay),
+            self.reconnect_due.clone(),
+        ));
+    }
+
+    // The server has let us in, so the next drop is retried straight away.
+    pub fn set_joined(&mut self) {
+        self.joined = true;
+        self.failed_connections = 0;
+    }
+
+    pub fn is_joined(&self) -> bool {
+        self.joined
+    }
+
+    // Gives up on the connection without trying again.
+    pub fn close(&mut self) {
+        self.socket = None;
+        self.reconnect = None;
+    }
+
+    // Dropped if there's no connection.
+    pub fn send(&mut self, message: String) {
+        if let Some(socket) = &mut self.socket {
+            let text: Text = Ok(message);
+            socket.send(text);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::pbf::session::{parse_alphabet, verifier_for, DEFAULT_SYMBOLS};
+use crate::pbf::solver::{Guess, Verification};
+use crate::pbf::Scoring;
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use yew::{html, Component, ComponentLink, Html, NodeRef};
+
+// For puzzle authors: checks a set of clues has exactly one answer, and which of them could be left out.
+pub struct VerifyComponent {
+    link: ComponentLink<Self>,
+    // The clues last checked, with what they leave possible.
+    checked: Option<(Vec<Guess<char>>, Verification<char>)>,
+    error: Option<String>,
+    symbols_ref: NodeRef,
+    scoring_ref: NodeRef,
+    clues_ref: NodeRef,
+}
+
+pub enum Msg {
+    Check,
+}
+
+impl VerifyComponent {
+    fn check(&self) -> Result<(Vec<Guess<char>>, Verification<char>), String> {
+        let symbols =
+            parse_alphabet(&self.symbols_ref.cast::<HtmlInputElement>().unwrap().value())?;
+        let scoring = match self
+            .scoring_ref
+            .cast::<HtmlSelectElement>()
+            .unwrap()
+            .value()
+            .as_str()
+        {
+            "multiset" => Scoring::Multiset,
+            _ => Scoring::Set,
+        };
+        let text = self
+            .clues_ref
+            .cast::<HtmlTextAreaElement>()
+            .unwrap()
+            .value();
+        let (solver, clues) = verifier_for(&text, &symbols, scoring)?;
+        let verification = solver.verify(&clues)?;
+        Ok((clues, verification))
+    }
+
+    fn render_checked(&self, clues: &[Guess<char>], verification: &Verification<char>) -> Html {
+        let summary = match verification.solution_count {
+            0 => "No secret fits the clues.".to_string(),
+            1 => "Exactly one secret fits the clues.".to_string(),
+            count => format!("{} secrets fit the clues.", count),
+        };
+        let mut solutions: Vec<String> = verification
+            .solutions
+            .iter()
+            .map(|solution| solution.iter().collect())
+            .collect();
+        solutions.sort();
+        let more_html = if solutions.len() < verification.solution_count {
+            html! {<p>{format!("and {} more", verification.solution_count - solutions.len())}</p>}
+        } else {
+            html! {}
+        };
+        html! {
+            <div>
+                <p class="status">{summary}</p>
+                <ul class="solutions">
+                    {for solutions.iter().map(|solution| html! {<li>{solution}</li>})}
+                </ul>
+                {more_html}
+                <h2>{"Clues"}</h2>
+                <p>{"Needed clues leave the same secrets between them. Each redundant one could be left out without letting any others in."}</p>
+                <ul>
+                    {for clues.iter().enumerate().map(|(i, clue)| html! {
+                        <li>
+                            {clue.guess.iter().collect::<String>()}{" - "}{clue.result}{": "}
+                            {if verification.necessary.contains(&i) { "needed" } else { "redundant" }}
+                        </li>
+                    })}
+                </ul>
+            </div>
+        }
+    }
+}
+
+impl Component for VerifyComponent {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            link,
+            checked: None,
+            error: None,
+            symbols_ref: NodeRef::default(),
+            scoring_ref: NodeRef::default(),
+            clues_ref: NodeRef::default(),
+        }
+    }
+    fn update(&mut self, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Check => match self.check() {
+                Ok(checked) => {
+                    self.checked = Some(checked);
+                    self.error = None;
+                }
+                Err(e) => {
+                    self.checked = None;
+                    self.error = Some(e);
+                }
+            },
+        }
+        true
+    }
+    fn change(&mut self, _: Self::Properties) -> bool {
+        false
+    }
+    fn view(&self) -> Html {
+        html! {
+            <div>
+                <h1>{"Check a puzzle"}</h1>
+                <p>{"Enter the clues of a puzzle, one guess and its outcome per line like \"123 fp\", to see every secret that fits them and which clues aren't needed."}</p>
+                <label for="verify_symbols">{"Symbols"}</label>
+                <input type="text" id="verify_symbols" ref=self.symbols_ref.clone() value=DEFAULT_SYMBOLS/>
+                <label for="verify_scoring">{"Repeated symbols"}</label>
+                <select id="verify_scoring" ref=self.scoring_ref.clone()>
+                    <option value="set" selected=true>{"Count once (Pico Bagel Fermi)"}</option>
+                    <option value="multiset">{"Count each time (Mastermind)"}</option>
+                </select>
+                <textarea rows="8" cols="20" id="verify_clues" ref=self.clues_ref.clone()/>
+                <input type="submit" value="Check clues" onclick=self.link.callback(|_| Msg::Check)/>
+                <pre class="error">{self.error.as_ref().unwrap_or(&"".to_string())}</pre>
+                {
+                    match &self.checked {
+                        Some((clues, verification)) => self.render_checked(clues, verification),
+                        None => html! {},
+                    }
+                }
+                <p><a href="/">{"Back to the main game"}</a></p>
+            </div>
+        }
+    }
+}
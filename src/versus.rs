@@ -0,0 +1,365 @@
+use crate::relay::RelayMessage;
+use crate::rules::Variant;
+use crate::secret::Secret;
+use crate::server_link::ServerLink;
+use crate::PbfStats;
+use web_sys::HtmlInputElement;
+use yew::format::Text;
+use yew::services::websocket::WebSocketStatus;
+use yew::{html, Component, ComponentLink, Html, NodeRef};
+
+// Where the relay from src/bin/relay.rs runs. Set PBF_RELAY_URL when building to use another one.
+const DEFAULT_RELAY_URL: &str = "ws://localhost:9001";
+
+// Identifies this player to the relay so a dropped connection can take the same seat back.
+pub fn random_player_id() -> String {
+    (0..4)
+        .map(|_| format!("{:04x}", (js_sys::Math::random() * 65536.0) as u32))
+        .collect()
+}
+
+fn get_value(node_ref: &NodeRef) -> String {
+    node_ref.cast::<HtmlInputElement>().unwrap().value()
+}
+
+// One player's side of a game, which carries on across reconnects.
+struct Game {
+    room: String,
+    player: String,
+    secret: Secret<char>,
+    secret_string: String,
+
+    // Known once the relay has seated us.
+    seat: Option<usize>,
+
+    // Everything sent to the other player, so whatever the relay missed while we were disconnected can be sent again.
+    outbox: Vec<RelayMessage>,
+
+    // How many of the other player's messages have been handled.
+    seen: usize,
+
+    opponent_length: Option<usize>,
+    opponent_connected: bool,
+    opponent_secret: Option<String>,
+
+    // Our guesses at the other player's secret. The result is None until they've scored it.
+    our_guesses: Vec<(String, Option<PbfStats>)>,
+    // Their guesses at our secret, with the result we gave.
+    their_guesses: Vec<(String, PbfStats)>,
+
+    // Whether we won, once someone has.
+    won: Option<bool>,
+}
+
+impl Game {
+    fn is_our_turn(&self) -> bool {
+        let seat = match self.seat {
+            Some(seat) if self.opponent_length.is_some() => seat,
+            _ => return false,
+        };
+        if self.won.is_some() || self.our_guesses.iter().any(|(_, result)| result.is_none()) {
+            return false;
+        }
+        // Seat 0 goes first, then the turns alternate.
+        if seat == 0 {
+            self.our_guesses.len() == self.their_guesses.len()
+        } else {
+            self.our_guesses.len() < self.their_guesses.len()
+        }
+    }
+}
+
+pub struct VersusComponent {
+    link: ComponentLink<Self>,
+    game: Option<Game>,
+
+    // Joined once the relay has seated us.
+    relay: ServerLink,
+
+    error: Option<String>,
+    room_input_ref: NodeRef,
+    secret_input_ref: NodeRef,
+    guess_input_ref: NodeRef,
+}
+
+pub enum Msg {
+    Start,
+    Connect,
+    Status(WebSocketStatus),
+    Received(Text),
+    Guess,
+}
+
+impl VersusComponent {
+    fn start(&mut self) -> Result<(), String> {
+        let room = get_value(&self.room_input_ref).trim().to_string();
+        if room.is_empty() || room.contains('\n') {
+            return Err("Enter a room name to share with the other player".to_string());
+        }
+        let secret = get_value(&self.secret_input_ref).trim().to_string();
+        if secret.is_empty() {
+            return Err("Enter a secret".to_string());
+        }
+        Variant::PicoBagelFermi.validate_secret(&secret)?;
+        let length = secret.chars().count();
+        self.game = Some(Game {
+            room,
+            player: random_player_id(),
+            secret: Secret::new(secret.chars().collect()),
+            secret_string: secret,
+            seat: None,
+            outbox: vec![RelayMessage::Ready { length }],
+            seen: 0,
+            opponent_length: None,
+            opponent_connected: false,
+            opponent_secret: None,
+            our_guesses: Vec::new(),
+            their_guesses: Vec::new(),
+            won: None,
+        });
+        self.connect();
+        Ok(())
+    }
+
+    fn connect(&mut self) {
+        if let Err(e) = self.relay.connect() {
+            self.error = Some(e);
+        }
+    }
+
+    fn send_raw(&mut self, message: &RelayMessage) {
+        self.relay.send(message.encode());
+    }
+
+    // Sent now if we're connected, otherwise once we've rejoined.
+    fn send(&mut self, message: RelayMessage) {
+        if let Some(game) = &mut self.game {
+            game.outbox.push(message.clone());
+            if self.relay.is_joined() {
+                self.send_raw(&message);
+            }
+        }
+    }
+
+    fn received(&mut self, message: RelayMessage) {
+        let game = match &mut self.game {
+            Some(game) => game,
+            None => return,
+        };
+        if message.is_forwarded() {
+            game.seen += 1;
+        }
+        match message {
+            RelayMessage::Joined { seat, received } => {
+                game.seat = Some(seat);
+                self.relay.set_joined();
+                self.error = None;
+                let unsent: Vec<RelayMessage> =
+                    game.outbox.iter().skip(received).cloned().collect();
+                for message in &unsent {
+                    self.send_raw(message);
+                }
+            }
+            RelayMessage::Peer { connected } => game.opponent_connected = connected,
+            RelayMessage::Error { message } => self.error = Some(message),
+            RelayMessage::Ready { length } => game.opponent_length = Some(length),
+            RelayMessage::Guess { guess } => {
+                let stats = game.secret.compare(guess.chars());
+                let cracked = stats.f as usize == game.secret.as_guess().len();
+                game.their_guesses.push((guess, stats));
+                self.send(RelayMessage::Result { stats });
+                if cracked {
+                    self.game_over(false);
+                }
+            }
+            RelayMessage::Result { stats } => {
+                if let Some((_, result)) = game
+                    .our_guesses
+                    .iter_mut()
+                    .find(|(_, result)| result.is_none())
+                {
+                    *result = Some(stats);
+                }
+                if Some(stats.f as usize) == game.opponent_length {
+                    self.game_over(true);
+                }
+            }
+            RelayMessage::Reveal { secret } => game.opponent_secret = Some(secret),
+            RelayMessage::Join { .. } => {}
+        }
+    }
+
+    fn game_over(&mut self, won: bool) {
+        let secret = match &mut self.game {
+            Some(game) if game.won.is_none() => {
+                game.won = Some(won);
+                game.secret_string.clone()
+            }
+            _ => return,
+        };
+        self.send(RelayMessage::Reveal { secret });
+    }
+
+    fn guess(&mut self) -> Result<(), String> {
+        let guess = get_value(&self.guess_input_ref).trim().to_string();
+        let game = match &mut self.game {
+            Some(game) if game.is_our_turn() => game,
+            _ => return Err("It isn't your turn".to_string()),
+        };
+        if Some(guess.chars().count()) != game.opponent_length {
+            return Err(format!(
+                "Guesses must be {} characters long",
+                game.opponent_length.unwrap_or(0)
+            ));
+        }
+        game.our_guesses.push((guess.clone(), None));
+        self.send(RelayMessage::Guess { guess });
+        Ok(())
+    }
+
+    fn render_status(&self, game: &Game) -> Html {
+        let status = if !self.relay.is_joined() {
+            "Connecting to the relay...".to_string()
+        } else if let Some(won) = game.won {
+            if won {
+                "You cracked their code first. You win!".to_string()
+            } else {
+                "They cracked your code first. You lose.".to_string()
+            }
+        } else if game.opponent_length.is_none() {
+            format!("Waiting for someone to join room {}...", game.room)
+        } else if !game.opponent_connected {
+            "The other player lost their connection. Waiting for them to come back...".to_string()
+        } else if game.is_our_turn() {
+            "Your turn.".to_string()
+        } else {
+            "Their turn.".to_string()
+        };
+        html! {<p class="status">{status}</p>}
+    }
+
+    fn render_game(&self, game: &Game) -> Html {
+        html! {
+            <div>
+                {self.render_status(game)}
+                <p>{format!("Your secret: {}", game.secret_string)}</p>
+                {
+                    match &game.opponent_secret {
+                        Some(secret) => html! {<p>{format!("Their secret was {}", secret)}</p>},
+                        None => html! {},
+                    }
+                }
+                <h2>{"Your guesses"}</h2>
+                <ul>
+                    {for game.our_guesses.iter().map(|(guess, result)| match result {
+                        Some(result) => html! {<li>{guess}{" - "}{result}</li>},
+                        None => html! {<li>{guess}{" - ..."}</li>},
+                    })}
+                </ul>
+                {
+                    if game.is_our_turn() {
+                        html! {
+                            <div>
+                                <label for="versus_guess">{"Guess"}</label>
+                                <input type="text" id="versus_guess" ref=self.guess_input_ref.clone()/>
+                                <input type="submit" value="Guess" onclick=self.link.callback(|_| Msg::Guess)/>
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                <h2>{"Their guesses"}</h2>
+                <ul>
+                    {for game.their_guesses.iter().map(|(guess, result)| html! {<li>{guess}{" - "}{result}</li>})}
+                </ul>
+            </div>
+        }
+    }
+}
+
+impl Component for VersusComponent {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            game: None,
+            relay: ServerLink::new(
+                option_env!("PBF_RELAY_URL").unwrap_or(DEFAULT_RELAY_URL),
+                link.callback(Msg::Received),
+                link.callback(Msg::Status),
+                link.callback(|_| Msg::Connect),
+            ),
+            link,
+            error: None,
+            room_input_ref: NodeRef::default(),
+            secret_input_ref: NodeRef::default(),
+            guess_input_ref: NodeRef::default(),
+        }
+    }
+    fn update(&mut self, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Start => {
+                if let Err(e) = self.start() {
+                    self.error = Some(e);
+                }
+            }
+            Msg::Connect => self.connect(),
+            Msg::Status(WebSocketStatus::Opened) => {
+                if let Some(game) = &self.game {
+                    let join = RelayMessage::Join {
+                        room: game.room.clone(),
+                        player: game.player.clone(),
+                        seen: game.seen,
+                    };
+                    self.send_raw(&join);
+                }
+            }
+            Msg::Status(_) => {
+                if self.game.as_ref().and_then(|game| game.won).is_none() {
+                    self.error =
+                        Some("Lost the connection to the relay. Reconnecting...".to_string());
+                    self.relay.schedule_reconnect();
+                }
+            }
+            Msg::Received(Ok(text)) => match RelayMessage::decode(&text) {
+                Ok(message) => self.received(message),
+                Err(e) => self.error = Some(e),
+            },
+            Msg::Received(Err(_)) => {}
+            Msg::Guess => match self.guess() {
+                Ok(()) => self.error = None,
+                Err(e) => self.error = Some(e),
+            },
+        }
+        true
+    }
+    fn change(&mut self, _: Self::Properties) -> bool {
+        false
+    }
+    fn view(&self) -> Html {
+        html! {
+            <div>
+                <h1>{"Head to head"}</h1>
+                <p>{"Each player picks a secret for the other to guess. You take turns guessing, and whoever cracks the other's code first wins."}</p>
+                {
+                    match &self.game {
+                        Some(game) => self.render_game(game),
+                        None => html! {
+                            <div>
+                                <label for="versus_room">{"Room"}</label>
+                                <input type="text" id="versus_room" ref=self.room_input_ref.clone()/>
+                                <label for="versus_secret">{"Your secret"}</label>
+                                <input type="text" id="versus_secret" ref=self.secret_input_ref.clone()/>
+                                <input type="submit" value="Play" onclick=self.link.callback(|_| Msg::Start)/>
+                            </div>
+                        },
+                    }
+                }
+                <span class="error">{self.error.as_ref().unwrap_or(&"".to_string())}</span>
+                <p><a href="/">{"Back to the main game"}</a></p>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+
+// Sorted, newline-separated lowercase words, zlib-compressed. To change a list, decompress it, edit it (keeping it sorted), and compress it again, e.g. with python's zlib.compress(data, 9).
+const COMPRESSED_DICTIONARIES: [(usize, &[u8]); 2] = [
+    (4, include_bytes!("../assets/words-4.txt.zz")),
+    (5, include_bytes!("../assets/words-5.txt.zz")),
+];
+
+lazy_static! {
+    // Map from word length to the words of that length. Only decompressed the first time a word game needs it.
+    static ref DICTIONARIES: HashMap<usize, Vec<String>> = COMPRESSED_DICTIONARIES
+        .iter()
+        .map(|(length, compressed)| {
+            let words = miniz_oxide::inflate::decompress_to_vec_zlib(compressed)
+                .expect("Bundled word list is corrupt");
+            let words = String::from_utf8(words).expect("Bundled word list is not utf-8");
+            (*length, words.lines().map(|word| word.to_string()).collect())
+        })
+        .collect();
+}
+
+pub fn word_lengths() -> Vec<usize> {
+    COMPRESSED_DICTIONARIES
+        .iter()
+        .map(|(length, _)| *length)
+        .collect()
+}
+
+pub fn dictionary(length: usize) -> Option<&'static Vec<String>> {
+    DICTIONARIES.get(&length)
+}
+
+pub fn is_word(word: &str) -> bool {
+    dictionary(word.chars().count())
+        .map(|words| words.binary_search_by(|w| w.as_str().cmp(word)).is_ok())
+        .unwrap_or(false)
+}